@@ -0,0 +1,75 @@
+//! Run a day 14 docking program and show what it wrote to memory
+//!
+//! ```text
+//! cargo run --bin bitmask -- --decoder 2 --trace --dump path/to/input
+//! ```
+
+use aoc_2020::day14::{parse_lines, BitmaskMachine, Decoder, V1, V2};
+use std::fs;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "bitmask", about = "Trace the day 14 bitmask machine")]
+struct Opt {
+    /// Which decoder chip version to use (1 or 2)
+    #[structopt(short, long, default_value = "1")]
+    decoder: u32,
+
+    /// Number of bits in a word
+    #[structopt(short, long, default_value = "36")]
+    width: u32,
+
+    /// Print every memory write as it happens
+    #[structopt(short, long)]
+    trace: bool,
+
+    /// Print the final memory contents, sorted by address
+    #[structopt(long)]
+    dump: bool,
+
+    /// Path to the puzzle input
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+}
+
+fn run(opt: &Opt, input: &str, decoder: impl Decoder) -> Result<(), String> {
+    let mut machine = BitmaskMachine::new(opt.width, decoder).map_err(|err| err.to_string())?;
+    if opt.trace {
+        machine = machine.with_trace();
+    }
+
+    let result = machine.run_lines(parse_lines(input));
+
+    // print the trace even if the program failed, since it shows how far it got
+    for write in machine.trace().unwrap_or_default() {
+        println!("{}", write.format(opt.width));
+    }
+    result.map_err(|err| err.to_string())?;
+
+    if opt.dump {
+        print!("{}", machine.memory().dump(opt.width));
+    }
+
+    println!("sum: {}", machine.memory().sum());
+    Ok(())
+}
+
+fn main() {
+    let opt = Opt::from_args();
+    let input = fs::read_to_string(&opt.input).unwrap_or_else(|err| {
+        eprintln!("failed to read {}: {}", opt.input.display(), err);
+        std::process::exit(1);
+    });
+
+    let result = match opt.decoder {
+        1 => run(&opt, &input, V1),
+        2 => run(&opt, &input, V2),
+        n => Err(format!("unknown decoder version: {}", n)),
+    };
+
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
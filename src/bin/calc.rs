@@ -0,0 +1,250 @@
+//! Evaluate expressions with the day 18 evaluator, one line at a time
+//!
+//! ```text
+//! cargo run --bin calc -- --mode standard
+//! ```
+//!
+//! Besides expressions, a line can be one of these commands:
+//!
+//! ```text
+//! :mode [part1|part2|standard]   show or change the precedence mode
+//! :rpn <expr>                    show the expression in reverse polish notation
+//! :tree <expr>                   show how the expression is grouped
+//! :help                          list the commands
+//! ```
+
+use aoc_2020::day18::{self, Error, PrecedenceTable};
+use std::fmt;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::str::FromStr;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "calc", about = "Evaluate expressions read from stdin")]
+struct Opt {
+    /// The operator precedence to start in: part1, part2 or standard
+    #[structopt(short, long, default_value = "standard")]
+    mode: Mode,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Part1,
+    Part2,
+    Standard,
+}
+
+impl Mode {
+    fn table(self) -> PrecedenceTable {
+        match self {
+            Mode::Part1 => PrecedenceTable::part1(),
+            Mode::Part2 => PrecedenceTable::part2(),
+            Mode::Standard => PrecedenceTable::standard(),
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "part1" => Ok(Mode::Part1),
+            "part2" => Ok(Mode::Part2),
+            "standard" => Ok(Mode::Standard),
+            _ => Err(format!(
+                "unknown mode '{}', expected part1, part2 or standard",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mode::Part1 => write!(f, "part1"),
+            Mode::Part2 => write!(f, "part2"),
+            Mode::Standard => write!(f, "standard"),
+        }
+    }
+}
+
+const HELP: &str = "\
+:mode [part1|part2|standard]   show or change the precedence mode
+:rpn <expr>                    show the expression in reverse polish notation
+:tree <expr>                   show how the expression is grouped
+:help                          list the commands";
+
+struct Calc {
+    mode: Mode,
+    table: PrecedenceTable,
+}
+
+impl Calc {
+    fn new(mode: Mode) -> Self {
+        Calc {
+            mode,
+            table: mode.table(),
+        }
+    }
+
+    /// Handle a line of input, numbered `line`
+    ///
+    /// Returns the text to print, or the diagnostic if the line was not understood.
+    fn run(&mut self, line: usize, input: &str) -> Result<String, String> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(String::new());
+        }
+
+        let (command, arg) = match input.strip_prefix(':') {
+            Some(command) => match command.split_once(char::is_whitespace) {
+                Some((command, arg)) => (Some(command), arg.trim()),
+                None => (Some(command), ""),
+            },
+            None => (None, input),
+        };
+
+        // syntax errors are reported against the expression, not the whole line
+        let error = |(span, kind)| {
+            Error {
+                line,
+                span: Some(span),
+                source: arg.to_string(),
+                kind,
+            }
+            .to_string()
+        };
+
+        match command {
+            None => day18::evaluate_line(line, arg, &self.table)
+                .map(|result| format!("{}\n", result.value))
+                .map_err(|err| err.to_string()),
+            Some("mode") if arg.is_empty() => Ok(format!("{}\n", self.mode)),
+            Some("mode") => {
+                *self = Calc::new(arg.parse()?);
+                Ok(String::new())
+            }
+            Some(command @ ("rpn" | "tree")) if arg.is_empty() => {
+                Err(format!("usage: :{} <expr>", command))
+            }
+            Some("rpn") => {
+                let rpn = day18::parse_rpn(arg, &self.table).map_err(error)?;
+                let rpn: Vec<String> = rpn.iter().map(|op| op.to_string()).collect();
+                Ok(format!("{}\n", rpn.join(" ")))
+            }
+            Some("tree") => Ok(day18::parse_expr(arg, &self.table).map_err(error)?.tree()),
+            Some("help") => Ok(format!("{}\n", HELP)),
+            Some(command) => Err(format!("unknown command ':{}', try :help", command)),
+        }
+    }
+}
+
+fn main() {
+    let opt = Opt::from_args();
+    let mut calc = Calc::new(opt.mode);
+
+    let stdin = io::stdin();
+    let interactive = stdin.is_terminal();
+    let prompt = || {
+        if interactive {
+            print!("> ");
+            io::stdout().flush().unwrap();
+        }
+    };
+
+    prompt();
+    for (i, line) in stdin.lock().lines().enumerate() {
+        let line = line.unwrap_or_else(|err| {
+            eprintln!("failed to read stdin: {}", err);
+            std::process::exit(1);
+        });
+        match calc.run(i + 1, &line) {
+            Ok(output) => print!("{}", output),
+            Err(err) => eprintln!("{}", err),
+        }
+        prompt();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Calc, Mode};
+
+    #[test]
+    pub fn test_evaluate() {
+        let mut calc = Calc::new(Mode::Standard);
+        assert_eq!(calc.run(1, "1 + 2 * 3"), Ok("7\n".to_string()));
+        assert_eq!(calc.run(2, "   "), Ok(String::new()));
+        assert_eq!(
+            calc.run(3, "2 * (3 +"),
+            Err("line 3, column 8: trailing operator '+'\n\
+                 \x20   2 * (3 +\n\
+                 \x20          ^"
+                .to_string())
+        );
+        assert_eq!(
+            calc.run(4, "1 / 0"),
+            Err("line 4: division by zero\n    1 / 0".to_string())
+        );
+    }
+
+    #[test]
+    pub fn test_mode() {
+        let mut calc = Calc::new(Mode::Standard);
+        assert_eq!(calc.run(1, ":mode"), Ok("standard\n".to_string()));
+
+        assert_eq!(calc.run(2, ":mode part1"), Ok(String::new()));
+        assert_eq!(calc.run(3, ":mode"), Ok("part1\n".to_string()));
+        assert_eq!(calc.run(4, "1 + 2 * 3"), Ok("9\n".to_string()));
+
+        assert_eq!(
+            calc.run(5, ":mode part3"),
+            Err("unknown mode 'part3', expected part1, part2 or standard".to_string())
+        );
+        assert_eq!(calc.run(6, ":mode"), Ok("part1\n".to_string()));
+    }
+
+    #[test]
+    pub fn test_rpn() {
+        let mut calc = Calc::new(Mode::Part2);
+        assert_eq!(calc.run(1, ":rpn 2 * 3 + 4"), Ok("2 3 4 + *\n".to_string()));
+        assert_eq!(
+            calc.run(2, ":rpn 1 $ 2"),
+            Err("line 2, column 3: unexpected char: '$'\n\
+                 \x20   1 $ 2\n\
+                 \x20     ^"
+                .to_string())
+        );
+    }
+
+    #[test]
+    pub fn test_tree() {
+        let mut calc = Calc::new(Mode::Part1);
+        assert_eq!(
+            calc.run(1, ":tree 1 + 2 * 3"),
+            Ok("*\n\
+                ├── +\n\
+                │   ├── 1\n\
+                │   └── 2\n\
+                └── 3\n"
+                .to_string())
+        );
+    }
+
+    #[test]
+    pub fn test_commands() {
+        let mut calc = Calc::new(Mode::Standard);
+        assert_eq!(calc.run(1, ":rpn"), Err("usage: :rpn <expr>".to_string()));
+        assert_eq!(
+            calc.run(2, ":tree  "),
+            Err("usage: :tree <expr>".to_string())
+        );
+        assert_eq!(
+            calc.run(3, ":bogus 1"),
+            Err("unknown command ':bogus', try :help".to_string())
+        );
+        assert!(calc.run(4, ":help").unwrap().starts_with(":mode"));
+    }
+}
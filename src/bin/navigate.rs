@@ -0,0 +1,108 @@
+//! Navigate the day 12 ferry and draw its path as an SVG image
+//!
+//! ```text
+//! cargo run --bin navigate -- --model waypoint --output path.svg path/to/input
+//! ```
+
+use aoc_2020::day12::{
+    navigate, parse_strict, svg, try_parse, ContinuousWaypoint, HeadingShip, Instruction,
+    Navigator, ParseError, Ship, Waypoint,
+};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "navigate", about = "Draw the path of the day 12 ferry")]
+struct Opt {
+    /// How the instructions are interpreted: ship, waypoint, heading or continuous-waypoint
+    ///
+    /// The heading and continuous-waypoint models allow turns of any angle.
+    #[structopt(short, long, default_value = "ship")]
+    model: Model,
+
+    /// Write the SVG to this file instead of stdout
+    #[structopt(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+
+    /// Path to the puzzle input
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+}
+
+#[derive(Clone, Copy)]
+enum Model {
+    Ship,
+    Waypoint,
+    Heading,
+    ContinuousWaypoint,
+}
+
+impl std::str::FromStr for Model {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ship" => Ok(Model::Ship),
+            "waypoint" => Ok(Model::Waypoint),
+            "heading" => Ok(Model::Heading),
+            "continuous-waypoint" => Ok(Model::ContinuousWaypoint),
+            _ => Err(format!(
+                "unknown model '{}', expected ship, waypoint, heading or continuous-waypoint",
+                s
+            )),
+        }
+    }
+}
+
+/// Run the navigator, and write the SVG to the output file or stdout
+fn run<N>(opt: &Opt, navigator: N, instructions: Vec<Instruction>) -> Result<(), String>
+where
+    N: Navigator,
+    N::Coord: Into<f64> + fmt::Display,
+{
+    let (navigator, trace) = navigate(navigator, instructions);
+
+    match &opt.output {
+        Some(path) => {
+            svg::write(&trace, path)
+                .map_err(|err| format!("failed to write {}: {}", path.display(), err))?;
+
+            let (x, y) = navigator.position();
+            println!("ended at ({}, {})", x, y);
+        }
+        None => print!("{}", svg::render(&trace)),
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let opt = Opt::from_args();
+    let input = fs::read_to_string(&opt.input).unwrap_or_else(|err| {
+        eprintln!("failed to read {}: {}", opt.input.display(), err);
+        std::process::exit(1);
+    });
+
+    let parse_error = |err: ParseError| err.to_string();
+    let result = match opt.model {
+        Model::Ship => parse_strict(&input)
+            .map_err(parse_error)
+            .and_then(|instructions| run(&opt, Ship::default(), instructions)),
+        Model::Waypoint => parse_strict(&input)
+            .map_err(parse_error)
+            .and_then(|instructions| run(&opt, Waypoint::default(), instructions)),
+        Model::Heading => try_parse(&input)
+            .map_err(parse_error)
+            .and_then(|instructions| run(&opt, HeadingShip::default(), instructions)),
+        Model::ContinuousWaypoint => try_parse(&input)
+            .map_err(parse_error)
+            .and_then(|instructions| run(&opt, ContinuousWaypoint::default(), instructions)),
+    };
+
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,69 @@
+//! Audit the day 16 ticket data
+//!
+//! ```text
+//! cargo run --bin tickets -- invalid --json path/to/input
+//! cargo run --bin tickets -- decode path/to/input
+//! ```
+
+use aoc_2020::day16;
+use std::fs;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "tickets", about = "Inspect the day 16 ticket notes")]
+enum Opt {
+    /// List the nearby tickets that have values which are not valid for any field
+    Invalid {
+        /// Print the report as JSON instead of text
+        #[structopt(long)]
+        json: bool,
+
+        /// Path to the puzzle input
+        #[structopt(parse(from_os_str))]
+        input: PathBuf,
+    },
+
+    /// Work out the fields of your ticket, and print them
+    Decode {
+        /// Path to the puzzle input
+        #[structopt(parse(from_os_str))]
+        input: PathBuf,
+    },
+}
+
+fn read(path: &PathBuf) -> String {
+    fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("failed to read {}: {}", path.display(), err);
+        std::process::exit(1);
+    })
+}
+
+fn exit(err: impl std::fmt::Display) -> ! {
+    eprintln!("{}", err);
+    std::process::exit(1);
+}
+
+fn main() {
+    match Opt::from_args() {
+        Opt::Invalid { json, input } => {
+            let input = read(&input);
+            let input = day16::parse_strict(&input).unwrap_or_else(|err| exit(err));
+
+            let report = day16::report::report(&input);
+            if json {
+                println!("{}", report.to_json());
+            } else {
+                print!("{}", report);
+            }
+        }
+        Opt::Decode { input } => {
+            let input = read(&input);
+            let input = day16::parse_strict(&input).unwrap_or_else(|err| exit(err));
+            print!(
+                "{}",
+                input.decode_my_ticket().unwrap_or_else(|err| exit(err))
+            );
+        }
+    }
+}
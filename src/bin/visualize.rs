@@ -0,0 +1,196 @@
+//! Watch the cellular automata of Advent of Code 2020 evolve in the terminal
+//!
+//! The main runner's command line is generated by `aoc_macro`, so the `--visualize` mode lives in
+//! this companion binary:
+//!
+//! ```text
+//! cargo run --bin visualize -- --visualize 11 --part 2 --fps 8 path/to/input
+//! ```
+
+use aoc_2020::{day11, day17};
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "visualize", about = "Animate the cellular automaton days")]
+struct Opt {
+    /// The day to visualize (11 or 17)
+    #[structopt(long)]
+    visualize: u32,
+
+    /// Which part's rules to use (1 or 2)
+    #[structopt(short, long, default_value = "1", parse(try_from_str = parse_part))]
+    part: u32,
+
+    /// Number of frames drawn per second
+    #[structopt(long, default_value = "10", parse(try_from_str = parse_fps))]
+    fps: f64,
+
+    /// Stop after this many generations, even if the automaton is still changing
+    #[structopt(short, long)]
+    generations: Option<usize>,
+
+    /// Path to the puzzle input
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+}
+
+fn parse_part(s: &str) -> Result<u32, String> {
+    match s {
+        "1" => Ok(1),
+        "2" => Ok(2),
+        _ => Err(format!("unknown part {}, expected 1 or 2", s)),
+    }
+}
+
+/// Parse a frame rate that gives a valid delay between frames
+fn parse_fps(s: &str) -> Result<f64, String> {
+    let fps: f64 = s.parse().map_err(|err| format!("{}", err))?;
+    if fps > 0.0 && Duration::try_from_secs_f64(1.0 / fps).is_ok() {
+        Ok(fps)
+    } else {
+        Err(format!(
+            "{} is not a positive number of frames per second",
+            s
+        ))
+    }
+}
+
+/// A cellular automaton that can be drawn one generation at a time
+///
+/// Adding a new day (e.g. day 24) is a matter of implementing this for its state type and adding
+/// it to `main`.
+trait Automaton {
+    /// Render the current generation
+    fn frame(&self) -> String;
+
+    /// Advance one generation. Returns false if nothing changed.
+    fn step(&mut self) -> bool;
+}
+
+impl Automaton for day11::Simulation {
+    fn frame(&self) -> String {
+        self.grid().to_string()
+    }
+
+    fn step(&mut self) -> bool {
+        day11::Simulation::step(self)
+    }
+}
+
+struct PocketDimension3(std::collections::HashSet<day17::XYZ>);
+struct PocketDimension4(std::collections::HashSet<day17::XYZW>);
+
+impl Automaton for PocketDimension3 {
+    fn frame(&self) -> String {
+        day17::render_slices(&self.0)
+    }
+
+    fn step(&mut self) -> bool {
+        let next = day17::cycle3(&self.0);
+        let changed = next != self.0;
+        self.0 = next;
+        changed
+    }
+}
+
+impl Automaton for PocketDimension4 {
+    fn frame(&self) -> String {
+        day17::render_slices(&self.0)
+    }
+
+    fn step(&mut self) -> bool {
+        let next = day17::cycle4(&self.0);
+        let changed = next != self.0;
+        self.0 = next;
+        changed
+    }
+}
+
+/// Draw generations until the automaton settles or the generation limit is hit
+///
+/// When stdout is a terminal, every frame is redrawn in place. Otherwise the frames are printed
+/// one after another.
+fn play(mut automaton: impl Automaton, fps: f64, generations: Option<usize>) -> io::Result<()> {
+    let stdout = io::stdout();
+    let animate = stdout.is_terminal();
+    let mut out = stdout.lock();
+    let delay = Duration::from_secs_f64(1.0 / fps);
+
+    if animate {
+        // hide the cursor
+        write!(out, "\x1b[?25l")?;
+    }
+
+    let mut generation = 0;
+    loop {
+        if animate {
+            // move the cursor home and clear the screen
+            write!(out, "\x1b[H\x1b[2J")?;
+        }
+        writeln!(out, "generation {}", generation)?;
+        writeln!(out, "{}", automaton.frame())?;
+        out.flush()?;
+
+        if Some(generation) == generations || !automaton.step() {
+            break;
+        }
+        generation += 1;
+
+        if animate {
+            thread::sleep(delay);
+        }
+    }
+
+    if animate {
+        // show the cursor again
+        write!(out, "\x1b[?25h")?;
+        out.flush()?;
+    }
+
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let opt = Opt::from_args();
+    let input = fs::read_to_string(&opt.input)?;
+
+    match (opt.visualize, opt.part) {
+        (11, part @ (1 | 2)) => {
+            let (neighborhood, tolerance) = match part {
+                1 => (day11::Neighborhood::Adjacent, 4),
+                _ => (day11::Neighborhood::LineOfSight, 5),
+            };
+            let simulation = day11::Simulation::new(&day11::parse(&input), neighborhood, tolerance);
+            play(simulation, opt.fps, opt.generations)
+        }
+        (17, 1) => {
+            let active = day17::parse(&input).map(|[x, y]| [x, y, 0]).collect();
+            play(
+                PocketDimension3(active),
+                opt.fps,
+                opt.generations.or(Some(6)),
+            )
+        }
+        (17, 2) => {
+            let active = day17::parse(&input).map(|[x, y]| [x, y, 0, 0]).collect();
+            play(
+                PocketDimension4(active),
+                opt.fps,
+                opt.generations.or(Some(6)),
+            )
+        }
+        (11 | 17, part) => {
+            eprintln!("day {} has no part {}", opt.visualize, part);
+            std::process::exit(1);
+        }
+        (day, _) => {
+            eprintln!("day {} is not a cellular automaton", day);
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use rayon::prelude::*;
 use std::collections::HashMap;
+use std::fmt;
+use std::mem;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Tile {
@@ -7,137 +9,385 @@ pub enum Tile {
     Seat { occupied: bool },
 }
 
-pub type Map = HashMap<(i32, i32), Tile>;
+/// A dense, row-major grid of tiles
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid {
+    width: usize,
+    height: usize,
+    tiles: Vec<Tile>,
+}
+
+/// Which seats a seat takes into account when deciding its next state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighborhood {
+    /// The eight immediately adjacent tiles
+    Adjacent,
 
-pub fn parse(input: &str) -> Map {
-    input
-        .lines()
-        .enumerate()
-        .flat_map(|(y, line)| line.chars().enumerate().map(move |(x, c)| (x, y, c)))
-        .map(|(x, y, c)| {
-            let tile = match c {
-                '.' => Tile::Floor,
-                '#' => Tile::Seat { occupied: true },
-                'L' => Tile::Seat { occupied: false },
-                _ => panic!("invalid tile char: '{}'", c),
-            };
-            ((x as i32, y as i32), tile)
-        })
-        .collect()
+    /// The first seat seen in each of the eight directions
+    LineOfSight,
 }
 
-pub fn part1(input: &str) -> usize {
-    let mut tiles = parse(input);
-
-    loop {
-        let mut new_tiles = tiles.clone();
-        new_tiles.par_iter_mut().for_each(|(&(x, y), tile)| {
-            let occupied_adjacent_seats = (x.saturating_sub(1)..=x + 1)
-                .flat_map(|x2| (y.saturating_sub(1)..=y + 1).map(move |y2| (x2, y2)))
-                .filter(|&(x2, y2)| x2 != x || y2 != y)
-                .filter_map(|(x2, y2)| tiles.get(&(x2, y2)).copied())
-                .filter(|tile| match tile {
-                    Tile::Seat { occupied } => *occupied,
-                    _ => false,
-                })
-                .count();
-
-            *tile = match *tile {
-                Tile::Seat { occupied: false } if occupied_adjacent_seats == 0 => {
-                    Tile::Seat { occupied: true }
-                }
-                Tile::Seat { occupied: true } if occupied_adjacent_seats >= 4 => {
-                    Tile::Seat { occupied: false }
-                }
-                _ => *tile,
-            };
-        });
+/// The seats visible from a single seat, as indices into the seat list
+#[derive(Debug, Clone, Copy, Default)]
+struct Visible {
+    count: u8,
+    seats: [u32; 8],
+}
 
-        if new_tiles == tiles {
-            break;
-        }
+/// A seating simulation over a fixed layout
+///
+/// Seat state is stored densely, one entry per seat, and double buffered so that each step only
+/// writes to the back buffer before swapping.
+pub struct Simulation {
+    /// The layout of the room, with seats in their initial state
+    layout: Grid,
+
+    /// The tile index of every seat in the layout
+    seats: Vec<usize>,
+
+    /// The precomputed visible seats of every seat
+    visible: Vec<Visible>,
+
+    /// How many visible occupied seats it takes for a person to leave their seat
+    tolerance: usize,
+
+    occupied: Vec<bool>,
+    next: Vec<bool>,
+}
+
+/// A simulation that stopped changing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stable {
+    /// The number of steps that changed the state before it settled
+    pub steps: usize,
 
-        tiles = new_tiles;
+    /// The number of occupied seats in the final state
+    pub occupied: usize,
+}
+
+/// A simulation that failed to settle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unstable {
+    /// The state after step `start` reappeared every `period` steps
+    Cycle { start: usize, period: usize },
+
+    /// The simulation was still changing after the given number of steps
+    StepLimit(usize),
+}
+
+const DIRECTIONS: [(isize, isize); 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+pub fn parse(input: &str) -> Grid {
+    let mut width = 0;
+    let mut height = 0;
+    let mut tiles = vec![];
+
+    for line in input.lines() {
+        width = line.len();
+        height += 1;
+        tiles.extend(line.chars().map(|c| match c {
+            '.' => Tile::Floor,
+            '#' => Tile::Seat { occupied: true },
+            'L' => Tile::Seat { occupied: false },
+            _ => panic!("invalid tile char: '{}'", c),
+        }));
     }
 
-    tiles
-        .values()
-        .filter(|tile| match tile {
-            Tile::Seat { occupied } => *occupied,
-            _ => false,
-        })
-        .count()
+    assert_eq!(tiles.len(), width * height, "grid is not rectangular");
+
+    Grid {
+        width,
+        height,
+        tiles,
+    }
 }
 
-pub fn part2(input: &str) -> usize {
-    let mut tiles = parse(input);
+impl Grid {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
 
-    loop {
-        let new_tiles = tiles
+    pub fn get(&self, x: usize, y: usize) -> Option<Tile> {
+        if x < self.width && y < self.height {
+            Some(self.tiles[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Take a step from (x, y) in the direction (dx, dy), if it's inside the grid
+    fn offset(&self, x: usize, y: usize, (dx, dy): (isize, isize)) -> Option<(usize, usize)> {
+        let x = x as isize + dx;
+        let y = y as isize + dy;
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            None
+        } else {
+            Some((x as usize, y as usize))
+        }
+    }
+}
+
+impl Simulation {
+    pub fn new(grid: &Grid, neighborhood: Neighborhood, tolerance: usize) -> Self {
+        // map every tile index to its index in the seat list
+        let mut seat_index = vec![None; grid.tiles.len()];
+        let mut seats = vec![];
+        let mut occupied = vec![];
+        for (i, tile) in grid.tiles.iter().enumerate() {
+            if let Tile::Seat { occupied: o } = tile {
+                seat_index[i] = Some(seats.len() as u32);
+                seats.push(i);
+                occupied.push(*o);
+            }
+        }
+
+        let visible = seats
             .iter()
-            .map(|(&(x, y), &tile)| {
-                let dirs = [
-                    (1, 0),
-                    (-1, 0),
-                    (0, 1),
-                    (0, -1),
-                    (1, 1),
-                    (1, -1),
-                    (-1, 1),
-                    (-1, -1),
-                ];
-
-                let mut occupied_adjacent_seats = 0;
-                for &(dx, dy) in dirs.iter() {
-                    let mut cx = x + dx;
-                    let mut cy = y + dy;
-
-                    while let Some(tile) = tiles.get(&(cx, cy)).copied() {
-                        if let Tile::Seat { occupied } = tile {
-                            if occupied {
-                                occupied_adjacent_seats += 1;
-                            }
+            .map(|&i| {
+                let (x, y) = (i % grid.width, i / grid.width);
+                let mut visible = Visible::default();
+
+                for &dir in DIRECTIONS.iter() {
+                    let mut pos = grid.offset(x, y, dir);
+                    while let Some((cx, cy)) = pos {
+                        if let Some(seat) = seat_index[cy * grid.width + cx] {
+                            visible.seats[visible.count as usize] = seat;
+                            visible.count += 1;
                             break;
                         }
 
-                        cx += dx;
-                        cy += dy;
+                        pos = match neighborhood {
+                            Neighborhood::Adjacent => None,
+                            Neighborhood::LineOfSight => grid.offset(cx, cy, dir),
+                        };
                     }
                 }
 
-                let tile = match tile {
-                    Tile::Seat { occupied: false } if occupied_adjacent_seats == 0 => {
-                        Tile::Seat { occupied: true }
-                    }
-                    Tile::Seat { occupied: true } if occupied_adjacent_seats >= 5 => {
-                        Tile::Seat { occupied: false }
-                    }
-                    _ => tile,
+                visible
+            })
+            .collect();
+
+        Simulation {
+            layout: grid.clone(),
+            next: occupied.clone(),
+            occupied,
+            seats,
+            visible,
+            tolerance,
+        }
+    }
+
+    /// Advance the simulation one step
+    ///
+    /// Returns whether any seat changed state
+    pub fn step(&mut self) -> bool {
+        let occupied = &self.occupied;
+        let tolerance = self.tolerance;
+
+        let changed = self
+            .next
+            .par_iter_mut()
+            .zip(self.visible.par_iter())
+            .zip(occupied.par_iter())
+            .map(|((next, visible), &was_occupied)| {
+                let occupied_visible_seats = visible.seats[..visible.count as usize]
+                    .iter()
+                    .filter(|&&seat| occupied[seat as usize])
+                    .count();
+
+                *next = if was_occupied {
+                    occupied_visible_seats < tolerance
+                } else {
+                    occupied_visible_seats == 0
                 };
 
-                ((x, y), tile)
+                *next != was_occupied
             })
-            .collect();
+            .reduce(|| false, |a, b| a || b);
+
+        mem::swap(&mut self.occupied, &mut self.next);
+
+        changed
+    }
+
+    /// Step the simulation until it stops changing
+    ///
+    /// Every visited state is kept, so that the simulation can bail out if it falls into a
+    /// cycle. If `step_limit` is set, the simulation may take at most that many state-changing
+    /// steps, and fails if the step after them still changes something.
+    pub fn run(&mut self, step_limit: Option<usize>) -> Result<Stable, Unstable> {
+        let mut seen = HashMap::new();
+        seen.insert(self.occupied.clone(), 0);
+
+        let mut steps = 0;
+        loop {
+            if !self.step() {
+                return Ok(Stable {
+                    steps,
+                    occupied: self.occupied(),
+                });
+            }
+
+            if Some(steps) == step_limit {
+                return Err(Unstable::StepLimit(steps));
+            }
+
+            steps += 1;
 
-        if new_tiles == tiles {
-            break;
+            if let Some(start) = seen.insert(self.occupied.clone(), steps) {
+                return Err(Unstable::Cycle {
+                    start,
+                    period: steps - start,
+                });
+            }
         }
+    }
+
+    /// The number of currently occupied seats
+    pub fn occupied(&self) -> usize {
+        self.occupied.iter().filter(|&&o| o).count()
+    }
 
-        tiles = new_tiles;
+    /// Render the current generation as a grid
+    pub fn grid(&self) -> Grid {
+        let mut grid = self.layout.clone();
+        for (&i, &occupied) in self.seats.iter().zip(self.occupied.iter()) {
+            grid.tiles[i] = Tile::Seat { occupied };
+        }
+        grid
     }
+}
 
-    tiles
-        .values()
-        .filter(|tile| match tile {
-            Tile::Seat { occupied } => *occupied,
-            _ => false,
-        })
-        .count()
+impl fmt::Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = match self {
+            Tile::Floor => '.',
+            Tile::Seat { occupied: true } => '#',
+            Tile::Seat { occupied: false } => 'L',
+        };
+        write!(f, "{}", c)
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // an empty grid has no rows to split the tiles into
+        if self.width == 0 {
+            return Ok(());
+        }
+
+        for row in self.tiles.chunks(self.width) {
+            for tile in row {
+                write!(f, "{}", tile)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Unstable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unstable::Cycle { start, period } => write!(
+                f,
+                "simulation entered a cycle after {} steps, with a period of {}",
+                start, period
+            ),
+            Unstable::StepLimit(limit) => {
+                write!(f, "simulation did not settle within {} steps", limit)
+            }
+        }
+    }
+}
+
+fn run(input: &str, neighborhood: Neighborhood, tolerance: usize) -> usize {
+    Simulation::new(&parse(input), neighborhood, tolerance)
+        .run(None)
+        .unwrap_or_else(|err| panic!("{}", err))
+        .occupied
+}
+
+pub fn part1(input: &str) -> usize {
+    run(input, Neighborhood::Adjacent, 4)
+}
+
+pub fn part2(input: &str) -> usize {
+    run(input, Neighborhood::LineOfSight, 5)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{parse, part1, part2, Neighborhood, Simulation, Stable, Unstable};
+
+    /// Check every generation of the simulation against a golden file
+    ///
+    /// The golden file contains one grid per generation, starting with the initial state, separated
+    /// by blank lines. The simulation is expected to have settled on the last one.
+    fn assert_generations(input: &str, neighborhood: Neighborhood, tolerance: usize, golden: &str) {
+        let mut simulation = Simulation::new(&parse(input), neighborhood, tolerance);
+
+        for (generation, frame) in golden.split("\n\n").enumerate() {
+            if generation > 0 {
+                simulation.step();
+            }
+
+            let expected = parse(frame);
+            let actual = simulation.grid();
+            assert_eq!(
+                (actual.width(), actual.height()),
+                (expected.width(), expected.height()),
+                "generation {} has the wrong dimensions",
+                generation
+            );
+
+            for y in 0..expected.height() {
+                for x in 0..expected.width() {
+                    assert_eq!(
+                        actual.get(x, y),
+                        expected.get(x, y),
+                        "generation {}: tile ({}, {}) differs\n{}",
+                        generation,
+                        x,
+                        y,
+                        actual,
+                    );
+                }
+            }
+        }
+
+        assert!(
+            !simulation.step(),
+            "simulation did not settle on the last frame"
+        );
+    }
+
+    #[test]
+    pub fn test_generations_part1() {
+        let input = include_str!("test-input");
+        let golden = include_str!("test-result");
+        assert_generations(input, Neighborhood::Adjacent, 4, golden);
+    }
+
+    #[test]
+    pub fn test_generations_part2() {
+        let input = include_str!("test-input");
+        let golden = include_str!("test-result2");
+        assert_generations(input, Neighborhood::LineOfSight, 5, golden);
+    }
 
     #[test]
     pub fn test_part1() {
@@ -150,4 +400,67 @@ mod tests {
         let input = include_str!("test-input");
         assert_eq!(part2(input), 26);
     }
+
+    #[test]
+    pub fn test_steps() {
+        let grid = parse(include_str!("test-input"));
+
+        let mut simulation = Simulation::new(&grid, Neighborhood::Adjacent, 4);
+        let stable = Stable {
+            steps: 5,
+            occupied: 37,
+        };
+        assert_eq!(simulation.run(None), Ok(stable));
+
+        let mut simulation = Simulation::new(&grid, Neighborhood::LineOfSight, 5);
+        let stable = Stable {
+            steps: 6,
+            occupied: 26,
+        };
+        assert_eq!(simulation.run(None), Ok(stable));
+    }
+
+    #[test]
+    pub fn test_step_limit() {
+        let grid = parse(include_str!("test-input"));
+        let mut simulation = Simulation::new(&grid, Neighborhood::Adjacent, 4);
+        assert_eq!(simulation.run(Some(3)), Err(Unstable::StepLimit(3)));
+
+        // the example settles after exactly 5 steps, so a limit of 5 is enough
+        let mut simulation = Simulation::new(&grid, Neighborhood::Adjacent, 4);
+        let stable = Stable {
+            steps: 5,
+            occupied: 37,
+        };
+        assert_eq!(simulation.run(Some(5)), Ok(stable));
+
+        let mut simulation = Simulation::new(&grid, Neighborhood::Adjacent, 4);
+        assert_eq!(simulation.run(Some(4)), Err(Unstable::StepLimit(4)));
+    }
+
+    #[test]
+    pub fn test_cycle() {
+        // two neighbors who can't stand each other will keep getting up and sitting down
+        let grid = parse("LL");
+        let mut simulation = Simulation::new(&grid, Neighborhood::Adjacent, 1);
+        let cycle = Unstable::Cycle {
+            start: 0,
+            period: 2,
+        };
+        assert_eq!(simulation.run(None), Err(cycle));
+    }
+
+    #[test]
+    pub fn test_empty() {
+        let grid = parse("");
+        assert_eq!(grid.to_string(), "");
+
+        let mut simulation = Simulation::new(&grid, Neighborhood::Adjacent, 4);
+        let stable = Stable {
+            steps: 0,
+            occupied: 0,
+        };
+        assert_eq!(simulation.run(None), Ok(stable));
+        assert_eq!(simulation.grid().to_string(), "");
+    }
 }
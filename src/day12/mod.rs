@@ -1,7 +1,12 @@
+pub mod svg;
+pub mod synthesize;
+
+use std::fmt;
+
 #[derive(Clone, Copy, Debug)]
 pub struct Instruction {
-    value: i32,
-    movement: Movement,
+    pub value: i32,
+    pub movement: Movement,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -12,7 +17,7 @@ pub enum Movement {
     Forward,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Direction {
     North,
     South,
@@ -20,27 +25,109 @@ pub enum Direction {
     East,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The line of the error, starting at 1
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    UnknownMovement(String),
+    InvalidValue(String),
+
+    /// A turn that isn't a multiple of 90 degrees
+    UnalignedTurn(i32),
+}
+
+fn parse_line(line: &str) -> Result<Instruction, ParseErrorKind> {
+    let mut chars = line.chars();
+    let movement = match chars.next() {
+        Some('N') => Movement::Direction(Direction::North),
+        Some('S') => Movement::Direction(Direction::South),
+        Some('W') => Movement::Direction(Direction::West),
+        Some('E') => Movement::Direction(Direction::East),
+        Some('L') => Movement::Left,
+        Some('R') => Movement::Right,
+        Some('F') => Movement::Forward,
+        other => {
+            let movement = other.map(String::from).unwrap_or_default();
+            return Err(ParseErrorKind::UnknownMovement(movement));
+        }
+    };
+
+    let value = chars.as_str();
+    Ok(Instruction {
+        value: value
+            .parse()
+            .map_err(|_| ParseErrorKind::InvalidValue(value.to_string()))?,
+        movement,
+    })
+}
+
+/// Parse the instructions, allowing turns of any angle
 pub fn parse<'a>(input: &'a str) -> impl Iterator<Item = Instruction> + 'a {
+    input.lines().enumerate().map(|(i, line)| {
+        parse_line(line).unwrap_or_else(|kind| panic!("{}", ParseError { line: i + 1, kind }))
+    })
+}
+
+/// Parse the instructions, allowing turns of any angle, and return the first line that fails
+pub fn try_parse(input: &str) -> Result<Vec<Instruction>, ParseError> {
     input
         .lines()
-        .map(|line| line.split_at(1))
-        .map(|(movement, value)| {
-            let movement = match movement {
-                "N" => Movement::Direction(Direction::North),
-                "S" => Movement::Direction(Direction::South),
-                "W" => Movement::Direction(Direction::West),
-                "E" => Movement::Direction(Direction::East),
-                "L" => Movement::Left,
-                "R" => Movement::Right,
-                "F" => Movement::Forward,
-                _ => panic!("non-recognised instruction: \"{}\"", movement),
-            };
+        .enumerate()
+        .map(|(i, line)| parse_line(line).map_err(|kind| ParseError { line: i + 1, kind }))
+        .collect()
+}
 
-            Instruction {
-                value: value.parse().unwrap(),
-                movement,
+/// Parse the instructions, rejecting any turn that isn't a multiple of 90 degrees
+pub fn parse_strict(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let error = |kind| ParseError { line: i + 1, kind };
+            let instruction = parse_line(line).map_err(error)?;
+            match instruction.movement {
+                Movement::Left | Movement::Right if instruction.value % 90 != 0 => {
+                    Err(error(ParseErrorKind::UnalignedTurn(instruction.value)))
+                }
+                _ => Ok(instruction),
             }
         })
+        .collect()
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ParseErrorKind::UnknownMovement(m) => {
+                write!(f, "non-recognised instruction: \"{}\"", m)
+            }
+            ParseErrorKind::InvalidValue(v) => write!(f, "invalid instruction value: \"{}\"", v),
+            ParseErrorKind::UnalignedTurn(degrees) => {
+                write!(f, "turn of {} degrees is not a multiple of 90", degrees)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let movement = match self.movement {
+            Movement::Direction(Direction::North) => 'N',
+            Movement::Direction(Direction::South) => 'S',
+            Movement::Direction(Direction::West) => 'W',
+            Movement::Direction(Direction::East) => 'E',
+            Movement::Left => 'L',
+            Movement::Right => 'R',
+            Movement::Forward => 'F',
+        };
+        write!(f, "{}{}", movement, self.value)
+    }
 }
 
 impl Direction {
@@ -53,13 +140,14 @@ impl Direction {
         }
     }
 
-    pub fn rotate(&self, mut degrees: i32) -> Self {
-        while degrees < 0 {
-            degrees = 360 + degrees;
-        }
+    /// Turn clockwise
+    ///
+    /// Panics if the turn isn't a multiple of 90 degrees.
+    pub fn rotate(&self, degrees: i32) -> Self {
+        assert_right_angle(degrees);
 
         let mut d = *self;
-        for _ in 0..(degrees / 90) {
+        for _ in 0..(degrees / 90).rem_euclid(4) {
             d = match d {
                 Direction::East => Direction::South,
                 Direction::South => Direction::West,
@@ -72,39 +160,135 @@ impl Direction {
     }
 }
 
-pub fn part1(input: &str) -> usize {
-    let instructions = parse(input);
+/// A model of how the ship interprets navigation instructions
+pub trait Navigator {
+    /// The type of the coordinates of the ship
+    type Coord: Copy;
+
+    /// Carry out a single instruction
+    fn navigate(&mut self, instruction: Instruction);
+
+    /// The current position of the ship
+    fn position(&self) -> (Self::Coord, Self::Coord);
 
-    let mut x = 0;
-    let mut y = 0;
-    let mut facing = Direction::East;
+    /// The current waypoint, relative to the ship, for models that have one
+    fn waypoint(&self) -> Option<(Self::Coord, Self::Coord)> {
+        None
+    }
+}
+
+/// The path taken while navigating
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trace<T = i32> {
+    /// The position of the ship before the first instruction and after every instruction
+    pub ship: Vec<(T, T)>,
 
-    let mut translate = |dir: Direction, value| {
-        let (dx, dy) = dir.as_delta();
-        x += dx * value;
-        y += dy * value;
+    /// The waypoint, relative to the ship, at the same points in time as `ship`
+    ///
+    /// Empty for models that don't use a waypoint.
+    pub waypoint: Vec<(T, T)>,
+}
+
+impl<T: Copy> Trace<T> {
+    fn record<N: Navigator<Coord = T>>(&mut self, navigator: &N) {
+        self.ship.push(navigator.position());
+        self.waypoint.extend(navigator.waypoint());
+    }
+}
+
+/// Run every instruction through a navigator, recording its path
+///
+/// Returns the navigator in its final state, along with the trace.
+pub fn navigate<N: Navigator>(
+    mut navigator: N,
+    instructions: impl IntoIterator<Item = Instruction>,
+) -> (N, Trace<N::Coord>) {
+    let mut trace = Trace {
+        ship: vec![],
+        waypoint: vec![],
     };
+    trace.record(&navigator);
 
     for instruction in instructions {
+        navigator.navigate(instruction);
+        trace.record(&navigator);
+    }
+
+    (navigator, trace)
+}
+
+/// Moves the ship itself, turning it in steps of 90 degrees
+///
+/// Panics on any other turn. Use [parse_strict] to reject those up front.
+#[derive(Clone, Copy, Debug)]
+pub struct Ship {
+    x: i32,
+    y: i32,
+    facing: Direction,
+}
+
+impl Default for Ship {
+    fn default() -> Self {
+        Ship {
+            x: 0,
+            y: 0,
+            facing: Direction::East,
+        }
+    }
+}
+
+impl Navigator for Ship {
+    type Coord = i32;
+
+    fn navigate(&mut self, instruction: Instruction) {
+        let mut translate = |dir: Direction, value| {
+            let (dx, dy) = dir.as_delta();
+            self.x += dx * value;
+            self.y += dy * value;
+        };
+
         match instruction.movement {
             Movement::Direction(direction) => {
                 translate(direction, instruction.value);
             }
-            Movement::Right => facing = facing.rotate(instruction.value),
-            Movement::Left => facing = facing.rotate(-instruction.value),
-            Movement::Forward => translate(facing, instruction.value),
+            Movement::Right => self.facing = self.facing.rotate(instruction.value),
+            Movement::Left => self.facing = self.facing.rotate(-instruction.value),
+            Movement::Forward => translate(self.facing, instruction.value),
         }
     }
 
+    fn position(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+}
+
+/// Parse the instructions for the integer models, and panic with the offending line if they
+/// contain a turn that isn't a right angle
+fn parse_right_angles(input: &str) -> Vec<Instruction> {
+    parse_strict(input).unwrap_or_else(|err| panic!("{}", err))
+}
+
+pub fn part1(input: &str) -> usize {
+    let (ship, _) = navigate(Ship::default(), parse_right_angles(input));
+    let (x, y) = ship.position();
     (x.abs() + y.abs()) as usize
 }
 
-fn rotate_delta(mut x: i32, mut y: i32, mut degrees: i32) -> (i32, i32) {
-    while degrees < 0 {
-        degrees = 360 + degrees;
-    }
+fn assert_right_angle(degrees: i32) {
+    assert!(
+        degrees % 90 == 0,
+        "turn of {} degrees is not a multiple of 90",
+        degrees
+    );
+}
+
+/// Rotate a vector clockwise
+///
+/// Panics if the turn isn't a multiple of 90 degrees.
+fn rotate_delta(mut x: i32, mut y: i32, degrees: i32) -> (i32, i32) {
+    assert_right_angle(degrees);
 
-    for _ in 0..(degrees / 90) {
+    for _ in 0..(degrees / 90).rem_euclid(4) {
         // (x, y) = (y, -x);
         let tmp = x;
         x = y;
@@ -114,45 +298,192 @@ fn rotate_delta(mut x: i32, mut y: i32, mut degrees: i32) -> (i32, i32) {
     (x, y)
 }
 
-pub fn part2(input: &str) -> usize {
-    let instructions = parse(input);
+/// Moves a waypoint around the ship, rotating it in steps of 90 degrees
+///
+/// Panics on any other turn. Use [parse_strict] to reject those up front.
+#[derive(Clone, Copy, Debug)]
+pub struct Waypoint {
+    x: i32,
+    y: i32,
+    wx: i32,
+    wy: i32,
+}
 
-    let mut x = 0;
-    let mut y = 0;
+impl Default for Waypoint {
+    fn default() -> Self {
+        Waypoint {
+            x: 0,
+            y: 0,
+            wx: 10,
+            wy: 1,
+        }
+    }
+}
 
-    let mut wx = 10;
-    let mut wy = 1;
+impl Navigator for Waypoint {
+    type Coord = i32;
 
-    for instruction in instructions {
+    fn navigate(&mut self, instruction: Instruction) {
         match instruction.movement {
             Movement::Direction(direction) => {
                 let (dx, dy) = direction.as_delta();
-                wx += dx * instruction.value;
-                wy += dy * instruction.value;
+                self.wx += dx * instruction.value;
+                self.wy += dy * instruction.value;
             }
             Movement::Right => {
-                let (new_wx, new_wy) = rotate_delta(wx, wy, instruction.value);
-                wx = new_wx;
-                wy = new_wy;
+                let (wx, wy) = rotate_delta(self.wx, self.wy, instruction.value);
+                self.wx = wx;
+                self.wy = wy;
             }
             Movement::Left => {
-                let (new_wx, new_wy) = rotate_delta(wx, wy, -instruction.value);
-                wx = new_wx;
-                wy = new_wy;
+                let (wx, wy) = rotate_delta(self.wx, self.wy, -instruction.value);
+                self.wx = wx;
+                self.wy = wy;
             }
             Movement::Forward => {
-                x += wx * instruction.value;
-                y += wy * instruction.value;
+                self.x += self.wx * instruction.value;
+                self.y += self.wy * instruction.value;
             }
         }
     }
 
+    fn position(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    fn waypoint(&self) -> Option<(i32, i32)> {
+        Some((self.wx, self.wy))
+    }
+}
+
+pub fn part2(input: &str) -> usize {
+    let (ship, _) = navigate(Waypoint::default(), parse_right_angles(input));
+    let (x, y) = ship.position();
     (x.abs() + y.abs()) as usize
 }
 
+/// The sine and cosine of an angle in degrees, exact for multiples of 90
+fn sin_cos(degrees: f64) -> (f64, f64) {
+    let degrees = degrees.rem_euclid(360.0);
+    if degrees == 0.0 {
+        (0.0, 1.0)
+    } else if degrees == 90.0 {
+        (1.0, 0.0)
+    } else if degrees == 180.0 {
+        (0.0, -1.0)
+    } else if degrees == 270.0 {
+        (-1.0, 0.0)
+    } else {
+        degrees.to_radians().sin_cos()
+    }
+}
+
+/// Rotate a vector clockwise by any angle
+fn rotate_delta_continuous(x: f64, y: f64, degrees: f64) -> (f64, f64) {
+    let (sin, cos) = sin_cos(degrees);
+    (x * cos + y * sin, y * cos - x * sin)
+}
+
+/// Moves the ship itself, with a heading that can point in any direction
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HeadingShip {
+    x: f64,
+    y: f64,
+
+    /// degrees clockwise from east
+    heading: f64,
+}
+
+impl Navigator for HeadingShip {
+    type Coord = f64;
+
+    fn navigate(&mut self, instruction: Instruction) {
+        let value = instruction.value as f64;
+        match instruction.movement {
+            Movement::Direction(direction) => {
+                let (dx, dy) = direction.as_delta();
+                self.x += dx as f64 * value;
+                self.y += dy as f64 * value;
+            }
+            Movement::Right => self.heading += value,
+            Movement::Left => self.heading -= value,
+            Movement::Forward => {
+                let (sin, cos) = sin_cos(self.heading);
+                self.x += cos * value;
+                self.y -= sin * value;
+            }
+        }
+    }
+
+    fn position(&self) -> (f64, f64) {
+        (self.x, self.y)
+    }
+}
+
+/// Moves a waypoint around the ship, where the waypoint can be rotated by any angle
+#[derive(Clone, Copy, Debug)]
+pub struct ContinuousWaypoint {
+    x: f64,
+    y: f64,
+    wx: f64,
+    wy: f64,
+}
+
+impl Default for ContinuousWaypoint {
+    fn default() -> Self {
+        ContinuousWaypoint {
+            x: 0.0,
+            y: 0.0,
+            wx: 10.0,
+            wy: 1.0,
+        }
+    }
+}
+
+impl Navigator for ContinuousWaypoint {
+    type Coord = f64;
+
+    fn navigate(&mut self, instruction: Instruction) {
+        let value = instruction.value as f64;
+        match instruction.movement {
+            Movement::Direction(direction) => {
+                let (dx, dy) = direction.as_delta();
+                self.wx += dx as f64 * value;
+                self.wy += dy as f64 * value;
+            }
+            Movement::Right => {
+                let (wx, wy) = rotate_delta_continuous(self.wx, self.wy, value);
+                self.wx = wx;
+                self.wy = wy;
+            }
+            Movement::Left => {
+                let (wx, wy) = rotate_delta_continuous(self.wx, self.wy, -value);
+                self.wx = wx;
+                self.wy = wy;
+            }
+            Movement::Forward => {
+                self.x += self.wx * value;
+                self.y += self.wy * value;
+            }
+        }
+    }
+
+    fn position(&self) -> (f64, f64) {
+        (self.x, self.y)
+    }
+
+    fn waypoint(&self) -> Option<(f64, f64)> {
+        Some((self.wx, self.wy))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, rotate_delta};
+    use super::{
+        navigate, parse, parse_strict, part1, part2, rotate_delta, try_parse, ContinuousWaypoint,
+        Direction, HeadingShip, Instruction, Movement, Navigator, ParseError, ParseErrorKind, Ship,
+        Waypoint,
+    };
 
     #[test]
     pub fn test_part1() {
@@ -180,4 +511,185 @@ mod tests {
         assert_eq!(rotate_delta(wx, wy, -450), (-1, 10));
         assert_eq!(rotate_delta(wx, wy, -360), (10, 1));
     }
+
+    #[test]
+    pub fn test_trace() {
+        let input = include_str!("test-input");
+
+        let (_, trace) = navigate(Ship::default(), parse(input));
+        assert_eq!(
+            trace.ship,
+            vec![(0, 0), (10, 0), (10, 3), (17, 3), (17, 3), (17, -8)]
+        );
+        assert!(trace.waypoint.is_empty());
+
+        let (_, trace) = navigate(Waypoint::default(), parse(input));
+        assert_eq!(
+            trace.ship,
+            vec![
+                (0, 0),
+                (100, 10),
+                (100, 10),
+                (170, 38),
+                (170, 38),
+                (214, -72)
+            ]
+        );
+        assert_eq!(
+            trace.waypoint,
+            vec![(10, 1), (10, 1), (10, 4), (10, 4), (4, -10), (4, -10)]
+        );
+    }
+
+    #[test]
+    pub fn test_try_parse() {
+        assert_eq!(try_parse("R45\nF1").map(|i| i.len()), Ok(2));
+
+        let error = ParseError {
+            line: 2,
+            kind: ParseErrorKind::InvalidValue("x".to_string()),
+        };
+        assert_eq!(try_parse("R45\nFx").unwrap_err(), error);
+    }
+
+    #[test]
+    pub fn test_parse_strict() {
+        let input = include_str!("test-input");
+        assert_eq!(parse_strict(input).map(|i| i.len()), Ok(5));
+
+        let error = ParseError {
+            line: 3,
+            kind: ParseErrorKind::UnalignedTurn(45),
+        };
+        assert_eq!(parse_strict("F10\nN3\nR45\nF1").unwrap_err(), error);
+        assert_eq!(
+            error.to_string(),
+            "line 3: turn of 45 degrees is not a multiple of 90"
+        );
+
+        let error = ParseError {
+            line: 2,
+            kind: ParseErrorKind::UnknownMovement("X".to_string()),
+        };
+        assert_eq!(parse_strict("F10\nX3").unwrap_err(), error);
+    }
+
+    #[test]
+    #[should_panic(expected = "line 1: turn of 45 degrees is not a multiple of 90")]
+    pub fn test_part1_unaligned() {
+        part1("R45\nF1");
+    }
+
+    #[test]
+    #[should_panic(expected = "line 2: turn of 135 degrees is not a multiple of 90")]
+    pub fn test_part2_unaligned() {
+        part2("F1\nL135");
+    }
+
+    #[test]
+    #[should_panic(expected = "turn of 45 degrees is not a multiple of 90")]
+    pub fn test_ship_unaligned() {
+        let turn = Instruction {
+            value: 45,
+            movement: Movement::Right,
+        };
+        Ship::default().navigate(turn);
+    }
+
+    #[test]
+    pub fn test_continuous() {
+        // right angles give the same result as the integer models
+        let input = include_str!("test-input");
+        let (ship, _) = navigate(HeadingShip::default(), parse(input));
+        assert_eq!(ship.position(), (17.0, -8.0));
+        let (ship, _) = navigate(ContinuousWaypoint::default(), parse(input));
+        assert_eq!(ship.position(), (214.0, -72.0));
+
+        let (ship, _) = navigate(HeadingShip::default(), parse("R45\nF10\nL135\nF10"));
+        let (x, y) = ship.position();
+        let d = 10.0 / 2f64.sqrt();
+        assert!((x - d).abs() < 1e-9, "{}", x);
+        assert!((y - (10.0 - d)).abs() < 1e-9, "{}", y);
+
+        let (ship, _) = navigate(ContinuousWaypoint::default(), parse("N9\nL45\nF1"));
+        let (x, y) = ship.position();
+        assert!((x - 0.0).abs() < 1e-9, "{}", x);
+        assert!((y - 200f64.sqrt()).abs() < 1e-9, "{}", y);
+    }
+
+    /// A waypoint, relative to the ship, that is dragged along when the ship moves forward
+    ///
+    /// Unlike [Waypoint], turns are ignored.
+    #[derive(Default)]
+    struct DraggedWaypoint {
+        ship: (i32, i32),
+        waypoint: (i32, i32),
+    }
+
+    impl Navigator for DraggedWaypoint {
+        type Coord = i32;
+
+        fn navigate(&mut self, instruction: Instruction) {
+            match instruction.movement {
+                Movement::Direction(direction) => {
+                    let (dx, dy) = direction.as_delta();
+                    self.waypoint.0 += dx * instruction.value;
+                    self.waypoint.1 += dy * instruction.value;
+                }
+                Movement::Forward => {
+                    self.ship.0 += self.waypoint.0 * instruction.value;
+                    self.ship.1 += self.waypoint.1 * instruction.value;
+                }
+                Movement::Left | Movement::Right => {}
+            }
+        }
+
+        fn position(&self) -> (i32, i32) {
+            self.ship
+        }
+
+        fn waypoint(&self) -> Option<(i32, i32)> {
+            Some(self.waypoint)
+        }
+    }
+
+    #[test]
+    pub fn test_custom_navigator() {
+        let instructions = vec![
+            Instruction {
+                value: 2,
+                movement: Movement::Direction(Direction::North),
+            },
+            Instruction {
+                value: 3,
+                movement: Movement::Forward,
+            },
+            Instruction {
+                value: 90,
+                movement: Movement::Right,
+            },
+            Instruction {
+                value: 1,
+                movement: Movement::Direction(Direction::East),
+            },
+            Instruction {
+                value: 2,
+                movement: Movement::Forward,
+            },
+        ];
+
+        let (navigator, trace) = navigate(DraggedWaypoint::default(), instructions);
+        // N2 puts the waypoint 2 north of the ship, F3 moves the ship 3 times that and keeps the
+        // waypoint 2 north of it, R90 is ignored, E1 moves the waypoint to (1, 2) and F2 moves
+        // the ship twice that
+        assert_eq!(navigator.position(), (2, 10));
+        assert_eq!(
+            trace.ship,
+            vec![(0, 0), (0, 0), (0, 6), (0, 6), (0, 6), (2, 10)]
+        );
+        assert_eq!(
+            trace.waypoint,
+            vec![(0, 0), (0, 2), (0, 2), (0, 2), (1, 2), (1, 2)]
+        );
+    }
 }
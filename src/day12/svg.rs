@@ -0,0 +1,126 @@
+//! Render navigation traces as SVG images
+
+use super::Trace;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Render a trace as a self-contained SVG image
+///
+/// The ship's path is drawn in blue and the absolute position of the waypoint, if any, in orange.
+/// The start is marked in green, the end in red, and the bounding box of everything is outlined.
+/// North is up.
+pub fn render<T: Copy + Into<f64>>(trace: &Trace<T>) -> String {
+    let point = |(x, y): (T, T)| -> (f64, f64) { (x.into(), y.into()) };
+    let ship: Vec<(f64, f64)> = trace.ship.iter().copied().map(point).collect();
+    let waypoint: Vec<(f64, f64)> = ship
+        .iter()
+        .zip(trace.waypoint.iter().copied().map(point))
+        .map(|(&(x, y), (wx, wy))| (x + wx, y + wy))
+        .collect();
+
+    let points = || ship.iter().chain(waypoint.iter());
+    let min_x = points().map(|&(x, _)| x).fold(f64::INFINITY, f64::min);
+    let max_x = points().map(|&(x, _)| x).fold(f64::NEG_INFINITY, f64::max);
+    let min_y = points().map(|&(_, y)| y).fold(f64::INFINITY, f64::min);
+    let max_y = points().map(|&(_, y)| y).fold(f64::NEG_INFINITY, f64::max);
+
+    let width = (max_x - min_x).max(1.0);
+    let height = (max_y - min_y).max(1.0);
+    let margin = width.max(height) / 20.0 + 1.0;
+    let marker = width.max(height) / 100.0 + 0.5;
+
+    // svg has y pointing down, so every y coordinate is flipped
+    // (adding 0 turns -0 into 0, which is nicer to read)
+    let flip = |y: f64| -y + 0.0;
+
+    let polyline = |points: &[(f64, f64)]| {
+        points
+            .iter()
+            .map(|&(x, y)| format!("{},{}", x, flip(y)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        min_x - margin,
+        flip(max_y) - margin,
+        width + 2.0 * margin,
+        height + 2.0 * margin,
+    );
+    let _ = writeln!(
+        svg,
+        r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="gray" stroke-dasharray="4 4" vector-effect="non-scaling-stroke"/>"#,
+        min_x,
+        flip(max_y),
+        width,
+        height,
+    );
+    if !waypoint.is_empty() {
+        let _ = writeln!(
+            svg,
+            r#"  <polyline points="{}" fill="none" stroke="orange" stroke-width="1" vector-effect="non-scaling-stroke"/>"#,
+            polyline(&waypoint),
+        );
+    }
+    let _ = writeln!(
+        svg,
+        r#"  <polyline points="{}" fill="none" stroke="blue" stroke-width="2" vector-effect="non-scaling-stroke"/>"#,
+        polyline(&ship),
+    );
+
+    let start = ship.first().copied().unwrap_or((0.0, 0.0));
+    let end = ship.last().copied().unwrap_or((0.0, 0.0));
+    for (class, color, (x, y)) in [("start", "green", start), ("end", "red", end)] {
+        let _ = writeln!(
+            svg,
+            r#"  <circle class="{}" cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+            class,
+            x,
+            flip(y),
+            marker,
+            color,
+        );
+    }
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+/// Render a trace and write it to an SVG file
+pub fn write<T: Copy + Into<f64>>(trace: &Trace<T>, path: impl AsRef<Path>) -> io::Result<()> {
+    fs::write(path, render(trace))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{navigate, parse, HeadingShip, Ship, Waypoint};
+    use super::render;
+
+    #[test]
+    pub fn test_render() {
+        let input = include_str!("test-input");
+
+        let (_, trace) = navigate(Ship::default(), parse(input));
+        let svg = render(&trace);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(r#"points="0,0 10,0 10,-3 17,-3 17,-3 17,8""#));
+        assert!(svg.contains(r#"class="start" cx="0" cy="0""#));
+        assert!(svg.contains(r#"class="end" cx="17" cy="8""#));
+        assert!(svg.contains(r#"<rect x="0" y="-3" width="17" height="11""#));
+        assert!(!svg.contains("orange"));
+
+        let (_, trace) = navigate(Waypoint::default(), parse(input));
+        let svg = render(&trace);
+        assert!(svg.contains(r#"class="end" cx="214" cy="72""#));
+        assert!(svg.contains("orange"));
+
+        let (_, trace) = navigate(HeadingShip::default(), parse("R45\nF2"));
+        let svg = render(&trace);
+        assert!(svg.contains(r#"class="end" cx="1.414"#));
+    }
+}
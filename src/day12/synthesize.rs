@@ -0,0 +1,130 @@
+//! Generate instruction lists with a known destination
+
+use super::{Direction, Instruction, Movement};
+
+fn instruction(movement: Movement, value: i32) -> Instruction {
+    Instruction { value, movement }
+}
+
+/// Move `distance` units along an axis, using `positive` or `negative` depending on the sign
+fn step(positive: Direction, negative: Direction, distance: i32) -> Option<Instruction> {
+    match distance {
+        0 => None,
+        d if d > 0 => Some(instruction(Movement::Direction(positive), d)),
+        d => Some(instruction(Movement::Direction(negative), -d)),
+    }
+}
+
+/// Instructions that take a [Ship](super::Ship) to `target`, optionally facing `heading` at the end
+pub fn ship_route(target: (i32, i32), heading: Option<Direction>) -> Vec<Instruction> {
+    let (x, y) = target;
+    let mut route: Vec<Instruction> = step(Direction::East, Direction::West, x)
+        .into_iter()
+        .chain(step(Direction::North, Direction::South, y))
+        .collect();
+
+    // the ship starts out facing east
+    match heading {
+        None | Some(Direction::East) => {}
+        Some(Direction::South) => route.push(instruction(Movement::Right, 90)),
+        Some(Direction::West) => route.push(instruction(Movement::Right, 180)),
+        Some(Direction::North) => route.push(instruction(Movement::Left, 90)),
+    }
+
+    route
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Instructions that take a [Waypoint](super::Waypoint) ship to `target`, optionally leaving the
+/// waypoint at `waypoint` at the end
+pub fn waypoint_route(target: (i32, i32), waypoint: Option<(i32, i32)>) -> Vec<Instruction> {
+    let (x, y) = target;
+    let mut route = vec![];
+
+    // the waypoint starts out at (10, 1)
+    let mut current = (10, 1);
+    let mut move_waypoint = |(wx, wy): (i32, i32), route: &mut Vec<Instruction>| {
+        route.extend(step(Direction::East, Direction::West, wx - current.0));
+        route.extend(step(Direction::North, Direction::South, wy - current.1));
+        current = (wx, wy);
+    };
+
+    if target != (0, 0) {
+        // the ship can only move in multiples of the waypoint, so point it at the target and go
+        // as many times as possible, to keep the numbers small
+        let times = gcd(x, y);
+        move_waypoint((x / times, y / times), &mut route);
+        route.push(instruction(Movement::Forward, times));
+    }
+
+    if let Some(waypoint) = waypoint {
+        move_waypoint(waypoint, &mut route);
+    }
+
+    route
+}
+
+/// Write instructions in the puzzle input format
+pub fn to_input(instructions: &[Instruction]) -> String {
+    instructions
+        .iter()
+        .map(|instruction| format!("{}\n", instruction))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{navigate, parse_strict, Direction, Navigator, Ship, Waypoint};
+    use super::{ship_route, to_input, waypoint_route};
+
+    const TARGETS: &[(i32, i32)] = &[(0, 0), (17, -8), (-5, 0), (0, 12), (-214, -72), (3, 7)];
+
+    const HEADINGS: &[Option<Direction>] = &[
+        None,
+        Some(Direction::North),
+        Some(Direction::South),
+        Some(Direction::East),
+        Some(Direction::West),
+    ];
+
+    #[test]
+    pub fn test_ship_route() {
+        for &target in TARGETS {
+            for &heading in HEADINGS {
+                let input = to_input(&ship_route(target, heading));
+                let instructions = parse_strict(&input).expect("generated invalid input");
+                assert!(instructions.len() <= 3, "route is too long:\n{}", input);
+
+                let (ship, _) = navigate(Ship::default(), instructions);
+                assert_eq!(ship.position(), target, "input was:\n{}", input);
+                if let Some(heading) = heading {
+                    assert_eq!(ship.facing, heading, "input was:\n{}", input);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_waypoint_route() {
+        for &target in TARGETS {
+            for &waypoint in &[None, Some((10, 1)), Some((0, 0)), Some((-3, 4))] {
+                let input = to_input(&waypoint_route(target, waypoint));
+                let instructions = parse_strict(&input).expect("generated invalid input");
+                assert!(instructions.len() <= 5, "route is too long:\n{}", input);
+
+                let (ship, _) = navigate(Waypoint::default(), instructions);
+                assert_eq!(ship.position(), target, "input was:\n{}", input);
+                if let Some(waypoint) = waypoint {
+                    assert_eq!(ship.waypoint(), Some(waypoint), "input was:\n{}", input);
+                }
+            }
+        }
+    }
+}
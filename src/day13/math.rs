@@ -0,0 +1,178 @@
+//! Modular arithmetic, and a solver for systems of congruences
+
+use std::fmt;
+
+/// The congruence `x ≡ remainder (mod modulus)`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Congruence {
+    pub remainder: u128,
+    pub modulus: u128,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CrtError {
+    /// No number satisfies both of these congruences
+    ///
+    /// The first one is the combination of every congruence before the second one.
+    NoSolution(Congruence, Congruence),
+
+    /// The combined modulus does not fit in 128 bits
+    Overflow,
+
+    /// A congruence had a modulus of zero
+    ZeroModulus,
+}
+
+/// The extended Euclidean algorithm
+///
+/// Returns `(g, x, y)` such that `a*x + b*y = g = gcd(a, b)`
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// Find `x` such that `a*x ≡ 1 (mod m)`, if `a` and `m` are coprime
+pub fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+    let (g, x, _) = extended_gcd(a.rem_euclid(m), m);
+    if g == 1 {
+        Some(x.rem_euclid(m))
+    } else {
+        None
+    }
+}
+
+impl Congruence {
+    pub fn new(remainder: u128, modulus: u128) -> Self {
+        Congruence { remainder, modulus }
+    }
+
+    /// Combine two congruences into one that holds exactly when both of them do
+    ///
+    /// The moduli do not need to be coprime.
+    pub fn combine(self, other: Congruence) -> Result<Congruence, CrtError> {
+        if self.modulus == 0 || other.modulus == 0 {
+            return Err(CrtError::ZeroModulus);
+        }
+
+        let int = |n: u128| i128::try_from(n).map_err(|_| CrtError::Overflow);
+        let (n1, n2) = (int(self.modulus)?, int(other.modulus)?);
+        let (a1, a2) = (
+            int(self.remainder % self.modulus)?,
+            int(other.remainder % other.modulus)?,
+        );
+
+        let (g, _, _) = extended_gcd(n1, n2);
+        if (a2 - a1) % g != 0 {
+            return Err(CrtError::NoSolution(self, other));
+        }
+
+        // x = a1 + n1 * k, where n1 * k ≡ a2 - a1 (mod n2)
+        // dividing by g leaves coprime moduli, so n1 / g can be inverted
+        let m = n2 / g;
+        let inverse = mod_inverse(n1 / g, m).expect("n1 / g and n2 / g are coprime");
+        let k = ((a2 - a1) / g % m)
+            .checked_mul(inverse)
+            .ok_or(CrtError::Overflow)?
+            .rem_euclid(m);
+        let lcm = n1.checked_mul(m).ok_or(CrtError::Overflow)?;
+        let x = n1
+            .checked_mul(k)
+            .and_then(|n1k| n1k.checked_add(a1))
+            .ok_or(CrtError::Overflow)?
+            .rem_euclid(lcm);
+
+        Ok(Congruence::new(x as u128, lcm as u128))
+    }
+}
+
+/// Solve a system of congruences using the Chinese remainder theorem
+///
+/// The solution is itself a congruence: the smallest non-negative solution, and the modulus at
+/// which it repeats. An empty system is satisfied by every number.
+pub fn crt(congruences: impl IntoIterator<Item = Congruence>) -> Result<Congruence, CrtError> {
+    congruences
+        .into_iter()
+        .try_fold(Congruence::new(0, 1), Congruence::combine)
+}
+
+impl fmt::Display for Congruence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "x ≡ {} (mod {})", self.remainder, self.modulus)
+    }
+}
+
+impl fmt::Display for CrtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrtError::NoSolution(a, b) => write!(f, "no solution satisfies both {} and {}", a, b),
+            CrtError::Overflow => write!(f, "the combined modulus overflowed"),
+            CrtError::ZeroModulus => write!(f, "modulus can not be zero"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crt, extended_gcd, mod_inverse, Congruence, CrtError};
+
+    #[test]
+    pub fn test_extended_gcd() {
+        assert_eq!(extended_gcd(240, 46), (2, -9, 47));
+        assert_eq!(extended_gcd(17, 5), (1, -2, 7));
+        assert_eq!(extended_gcd(0, 5), (5, 0, 1));
+    }
+
+    #[test]
+    pub fn test_mod_inverse() {
+        assert_eq!(mod_inverse(3, 11), Some(4));
+        assert_eq!(mod_inverse(-3, 11), Some(7));
+        assert_eq!(mod_inverse(6, 9), None);
+    }
+
+    #[test]
+    pub fn test_crt() {
+        let c = Congruence::new;
+        assert_eq!(crt(vec![c(2, 3), c(3, 5), c(2, 7)]), Ok(c(23, 105)));
+
+        // not coprime, but compatible
+        assert_eq!(crt(vec![c(0, 4), c(4, 6)]), Ok(c(4, 12)));
+
+        // not coprime, and incompatible
+        assert_eq!(
+            crt(vec![c(0, 4), c(5, 6)]),
+            Err(CrtError::NoSolution(c(0, 4), c(5, 6)))
+        );
+
+        // the remainder may be larger than the modulus
+        assert_eq!(crt(vec![c(7, 3)]), Ok(c(1, 3)));
+
+        assert_eq!(crt(vec![]), Ok(c(0, 1)));
+        assert_eq!(crt(vec![c(1, 0)]), Err(CrtError::ZeroModulus));
+    }
+
+    #[test]
+    pub fn test_crt_overflow() {
+        // large primes, whose product does not fit in 128 bits
+        let primes = [
+            18446744073709551557,
+            18446744073709551533,
+            18446744073709551521,
+        ];
+        let congruences = primes.iter().map(|&p| Congruence::new(1, p));
+        assert_eq!(crt(congruences), Err(CrtError::Overflow));
+    }
+}
@@ -1,3 +1,9 @@
+pub mod math;
+
+use math::{crt, Congruence, CrtError};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
 pub struct Data {
     /// earliest timestamp you could depart on a bus
     earliest: u64,
@@ -5,12 +11,29 @@ pub struct Data {
     busses: Vec<Bus>,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Bus {
     id: u64,
     offset: u64,
 }
 
+/// A bus leaving the station
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Departure {
+    pub time: u64,
+    pub bus: u64,
+}
+
+/// When a group of busses departs with the requested offsets
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Alignment {
+    /// The first time the busses line up
+    pub first: u128,
+
+    /// How often the busses line up again after `first`
+    pub period: u128,
+}
+
 pub fn parse(input: &str) -> Data {
     let mut offset = 0;
     Data {
@@ -24,10 +47,11 @@ pub fn parse(input: &str) -> Data {
             .filter_map(|line| {
                 let r = match line {
                     "x" => None,
-                    num => Some(Bus {
-                        offset,
-                        id: num.parse().unwrap(),
-                    }),
+                    num => {
+                        let id = num.parse().unwrap();
+                        assert_ne!(id, 0, "bus id can not be 0");
+                        Some(Bus { offset, id })
+                    }
                 };
                 offset += 1;
                 r
@@ -36,52 +60,111 @@ pub fn parse(input: &str) -> Data {
     }
 }
 
-pub fn part1(input: &str) -> u64 {
-    let data = parse(input);
-    data.busses
-        .iter()
-        .map(|bus| bus.id)
-        .map(|id| {
-            let next_departure = id - (data.earliest % id);
-            (next_departure, id)
-        })
-        .min()
-        .map(|(wait, id)| wait * id)
-        .expect("failed to find a solution")
+impl Bus {
+    pub fn new(id: u64, offset: u64) -> Self {
+        Bus { id, offset }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// How long after the start of a sequence the bus should depart
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// The first time at or after `t` that the bus departs
+    ///
+    /// A bus with id 0 never departs.
+    pub fn next_departure(&self, t: u64) -> Option<u64> {
+        if self.id == 0 {
+            return None;
+        }
+        Some(t.div_ceil(self.id) * self.id)
+    }
 }
 
-pub fn part2(input: &str) -> u64 {
-    let data = parse(input);
+impl Data {
+    pub fn earliest(&self) -> u64 {
+        self.earliest
+    }
+
+    pub fn busses(&self) -> &[Bus] {
+        &self.busses
+    }
 
-    // solve chinese remainder theorem
-    // method shamelessly stolen from online math course
+    /// The next `n` departures of any bus, at or after time `t`, in order
+    pub fn next_departures(&self, t: u64, n: usize) -> Vec<Departure> {
+        let mut queue: BinaryHeap<Reverse<Departure>> = self
+            .busses
+            .iter()
+            .filter_map(|bus| {
+                Some(Reverse(Departure {
+                    time: bus.next_departure(t)?,
+                    bus: bus.id,
+                }))
+            })
+            .collect();
 
-    fn solve_xi(n: u64, m: u64) -> u64 {
-        let mut x = 1;
-        let mut nx = n;
-        while nx % m != 1 {
-            x += 1;
-            nx += n;
+        let mut departures = Vec::with_capacity(n);
+        while departures.len() < n {
+            let Reverse(departure) = match queue.pop() {
+                Some(departure) => departure,
+                None => break,
+            };
+            departures.push(departure);
+            queue.push(Reverse(Departure {
+                time: departure.time + departure.bus,
+                bus: departure.bus,
+            }));
         }
-        return x;
+
+        departures
     }
 
-    let n: u64 = data.busses.iter().map(|bus| bus.id).product();
+    /// Find when the given busses line up, so that each bus departs at its offset from the start
+    pub fn alignment(&self, busses: impl IntoIterator<Item = Bus>) -> Result<Alignment, CrtError> {
+        // bus i departs at t + offset_i, i.e. t ≡ -offset_i (mod id_i)
+        let congruences = busses
+            .into_iter()
+            .map(|bus| {
+                let id = bus.id as u128;
+                if id == 0 {
+                    return Err(CrtError::ZeroModulus);
+                }
+                let offset = bus.offset as u128 % id;
+                Ok(Congruence::new((id - offset) % id, id))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
-    let mut bnx = 0;
-    for bus in data.busses.into_iter().skip(1) {
-        let i = bus.offset;
-        let ni = n / bus.id;
-        let xi = solve_xi(ni, bus.id);
-        bnx += i * ni * xi;
+        let solution = crt(congruences)?;
+        Ok(Alignment {
+            first: solution.remainder,
+            period: solution.modulus,
+        })
     }
+}
 
-    n - (bnx % n)
+pub fn part1(input: &str) -> u64 {
+    let data = parse(input);
+    data.next_departures(data.earliest, 1)
+        .first()
+        .map(|departure| (departure.time - data.earliest) * departure.bus)
+        .expect("failed to find a solution")
+}
+
+pub fn part2(input: &str) -> u64 {
+    let data = parse(input);
+    let alignment = data
+        .alignment(data.busses.iter().copied())
+        .unwrap_or_else(|err| panic!("{}", err));
+    u64::try_from(alignment.first).expect("solution does not fit in a u64")
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{parse, part1, part2, Alignment, Bus, CrtError, Departure};
 
     #[test]
     pub fn test_part1() {
@@ -94,4 +177,77 @@ mod tests {
         let input = include_str!("test-input");
         assert_eq!(part2(input), 1068781);
     }
+
+    #[test]
+    pub fn test_part2_examples() {
+        let examples = [
+            ("17,x,13,19", 3417),
+            ("67,7,59,61", 754018),
+            ("67,x,7,59,61", 779210),
+            ("67,7,x,59,61", 1261476),
+            ("1789,37,47,1889", 1202161486),
+        ];
+
+        for &(busses, output) in examples.iter() {
+            let input = format!("0\n{}", busses);
+            assert_eq!(part2(&input), output, "busses were: {}", busses);
+        }
+    }
+
+    #[test]
+    pub fn test_next_departures() {
+        let data = parse(include_str!("test-input"));
+        let departure = |time, bus| Departure { time, bus };
+        assert_eq!(
+            data.next_departures(939, 5),
+            vec![
+                departure(944, 59),
+                departure(945, 7),
+                departure(949, 13),
+                departure(950, 19),
+                departure(952, 7),
+            ]
+        );
+        assert_eq!(data.next_departures(0, 1), vec![departure(0, 7)]);
+
+        assert_eq!(Bus::new(7, 0).next_departure(8), Some(14));
+        assert_eq!(Bus::new(0, 0).next_departure(8), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "bus id can not be 0")]
+    pub fn test_parse_zero() {
+        parse("939\n7,0,x,59");
+    }
+
+    #[test]
+    pub fn test_alignment() {
+        let data = parse(include_str!("test-input"));
+
+        let alignment = data.alignment(data.busses().iter().copied());
+        let expected = Alignment {
+            first: 1068781,
+            period: 7 * 13 * 59 * 31 * 19,
+        };
+        assert_eq!(alignment, Ok(expected));
+
+        // 7 and 13 departing at the same time
+        let alignment = data.alignment(vec![Bus::new(7, 0), Bus::new(13, 0)]);
+        let expected = Alignment {
+            first: 0,
+            period: 91,
+        };
+        assert_eq!(alignment, Ok(expected));
+
+        // 13 departing 2 minutes before 7
+        let alignment = data.alignment(vec![Bus::new(7, 2), Bus::new(13, 0)]);
+        let expected = Alignment {
+            first: 26,
+            period: 91,
+        };
+        assert_eq!(alignment, Ok(expected));
+
+        let alignment = data.alignment(vec![Bus::new(7, 2), Bus::new(0, 0)]);
+        assert_eq!(alignment, Err(CrtError::ZeroModulus));
+    }
 }
@@ -1,83 +1,420 @@
 use std::collections::HashMap;
+use std::fmt;
 
-struct Memory {
-    data: HashMap<u64, u64>,
+/// A set of addresses, where the `floating` bits can take on any value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    /// The fixed bits of the address. Floating bits are always 0.
+    address: u64,
+    floating: u64,
 }
 
+/// Memory that stores writes as address patterns instead of expanding them
+///
+/// The stored patterns never overlap: when a new pattern is written, it is first subtracted from
+/// every older pattern that it intersects.
+#[derive(Default)]
+pub struct Memory {
+    /// Values at single addresses
+    fixed: HashMap<u64, u64>,
+
+    /// Values of patterns with at least one floating bit
+    floating: Vec<(Pattern, u64)>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Mask {
+    pub ones: u64,
+    pub zeros: u64,
+    pub xes: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Instr {
-    Mask { ones: u64, zeros: u64, xes: u64 },
+    Mask(Mask),
     Mem { at: u64, value: u64 },
 }
 
+/// A version of the decoder chip, which decides how the mask is applied when writing to memory
+pub trait Decoder {
+    /// The addresses and the value that a `mem[at] = value` instruction writes
+    fn decode(&self, mask: &Mask, at: u64, value: u64) -> (Pattern, u64);
+}
+
+/// Version 1: the mask overwrites bits of the value
+pub struct V1;
+
+/// Version 2: the mask overwrites bits of the address, and X bits are floating
+pub struct V2;
+
+/// A docking program computer with a configurable word width and decoder version
+pub struct BitmaskMachine<D> {
+    width: u32,
+    decoder: D,
+    mask: Mask,
+
+    /// The input line of the active mask, if known
+    mask_line: Option<usize>,
+
+    memory: Memory,
+
+    /// Every write made to memory, if tracing is enabled
+    trace: Option<Vec<TracedWrite>>,
+}
+
+/// A memory write made by the machine
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TracedWrite {
+    /// The input line of the mask that was active during the write
+    pub mask_line: Option<usize>,
+    pub pattern: Pattern,
+    pub value: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MachineError {
+    /// The word width must be between 1 and 64 bits
+    InvalidWidth(u32),
+    MaskTooWide(Mask),
+    AddressTooWide(u64),
+    ValueTooWide(u64),
+}
+
+impl Pattern {
+    pub fn new(address: u64, floating: u64) -> Self {
+        Pattern {
+            address: address & !floating,
+            floating,
+        }
+    }
+
+    /// The number of addresses matched by this pattern
+    pub fn count(&self) -> u128 {
+        1 << self.floating.count_ones()
+    }
+
+    /// Format the pattern as an address
+    ///
+    /// A single address is written in decimal, and a pattern with floating bits is written as a
+    /// `width`-bit mask with `X` for the floating bits.
+    pub fn format(&self, width: u32) -> String {
+        if self.floating == 0 {
+            return self.address.to_string();
+        }
+
+        (0..width)
+            .rev()
+            .map(
+                |bit| match (self.floating >> bit & 1, self.address >> bit & 1) {
+                    (1, _) => 'X',
+                    (_, 1) => '1',
+                    _ => '0',
+                },
+            )
+            .collect()
+    }
+
+    pub fn contains(&self, address: u64) -> bool {
+        address & !self.floating == self.address
+    }
+
+    pub fn intersects(&self, other: &Pattern) -> bool {
+        let fixed = !self.floating & !other.floating;
+        (self.address ^ other.address) & fixed == 0
+    }
+
+    /// Push a set of disjoint patterns that together match everything in `self` but not in `other`
+    pub fn subtract(&self, other: &Pattern, out: &mut Vec<Pattern>) {
+        if !self.intersects(other) {
+            out.push(*self);
+            return;
+        }
+
+        // fix the floating bits of self that are fixed in other, one at a time. every time we fix
+        // a bit, the half with the opposite value of other is outside of it.
+        let mut rest = *self;
+        let mut to_fix = self.floating & !other.floating;
+        while to_fix != 0 {
+            let bit = to_fix & to_fix.wrapping_neg();
+            to_fix &= !bit;
+
+            rest.floating &= !bit;
+            out.push(Pattern {
+                address: rest.address | (!other.address & bit),
+                floating: rest.floating,
+            });
+            rest.address |= other.address & bit;
+        }
+
+        // what remains of self is now entirely inside other
+    }
+}
+
 impl Memory {
     pub fn new() -> Self {
-        Self {
-            data: HashMap::with_capacity(16000),
-        }
+        Self::default()
     }
 
     pub fn put(&mut self, at: u64, value: u64) {
-        self.data.insert(at, value);
+        self.put_pattern(Pattern::new(at, 0), value);
     }
 
-    pub fn sum(&self) -> u64 {
-        self.data.values().sum()
+    /// Write a value to every address matched by the pattern
+    pub fn put_pattern(&mut self, pattern: Pattern, value: u64) {
+        if pattern.floating != 0 {
+            self.fixed.retain(|&at, _| !pattern.contains(at));
+        }
+
+        // only the patterns that intersect the new one need to be split
+        let mut overlapped = vec![];
+        self.floating.retain(|&(old, old_value)| {
+            let intersects = old.intersects(&pattern);
+            if intersects {
+                overlapped.push((old, old_value));
+            }
+            !intersects
+        });
+
+        let mut pieces = vec![];
+        for (old, old_value) in overlapped {
+            old.subtract(&pattern, &mut pieces);
+            for piece in pieces.drain(..) {
+                self.insert(piece, old_value);
+            }
+        }
+
+        self.insert(pattern, value);
+    }
+
+    /// Store a pattern that doesn't overlap any stored pattern
+    fn insert(&mut self, pattern: Pattern, value: u64) {
+        if pattern.floating == 0 {
+            self.fixed.insert(pattern.address, value);
+        } else {
+            self.floating.push((pattern, value));
+        }
+    }
+
+    /// The value at an address, if it has been written
+    pub fn get(&self, at: u64) -> Option<u64> {
+        self.fixed.get(&at).copied().or_else(|| {
+            self.floating
+                .iter()
+                .find(|(pattern, _)| pattern.contains(at))
+                .map(|&(_, value)| value)
+        })
+    }
+
+    /// Every stored pattern and its value, in no particular order
+    fn patterns(&self) -> impl Iterator<Item = (Pattern, u64)> + '_ {
+        let fixed = self
+            .fixed
+            .iter()
+            .map(|(&at, &value)| (Pattern::new(at, 0), value));
+        fixed.chain(self.floating.iter().copied())
     }
-}
 
-const BITS: usize = 36;
+    /// The sum of every value in memory
+    ///
+    /// With wide words, it may not fit in a `u64`. It always fits in a `u128`, since there are at
+    /// most 2^64 addresses with values below 2^64.
+    pub fn sum(&self) -> u128 {
+        self.patterns()
+            .map(|(pattern, value)| pattern.count() * value as u128)
+            .sum()
+    }
+
+    /// Every written pattern and its value, sorted by address, one per line
+    pub fn dump(&self, width: u32) -> String {
+        let mut data: Vec<(Pattern, u64)> = self.patterns().collect();
+        data.sort_unstable_by_key(|(pattern, _)| (pattern.address, pattern.floating));
+        data.iter()
+            .map(|(pattern, value)| format!("mem[{}] = {}\n", pattern.format(width), value))
+            .collect()
+    }
+}
 
 pub fn parse<'a>(input: &'a str) -> impl Iterator<Item = Instr> + 'a {
+    parse_lines(input).map(|(_, instr)| instr)
+}
+
+/// Parse the program, along with the line number (starting at 1) of every instruction
+pub fn parse_lines<'a>(input: &'a str) -> impl Iterator<Item = (usize, Instr)> + 'a {
     input
         .lines()
-        .filter_map(|line| line.split_once(" = "))
-        .map(|(op, value)| match op {
-            "mask" => {
-                let mut xes: u64 = 0;
-                let mut zeros: u64 = 0;
-                let mut ones: u64 = 0;
-
-                for (i, c) in value.chars().rev().enumerate() {
-                    match c {
-                        'X' => xes = set_bit(xes, i),
-                        '0' => zeros = set_bit(zeros, i),
-                        '1' => ones = set_bit(ones, i),
-                        _ => panic!("unknown mask character: {}", c),
+        .enumerate()
+        .filter_map(|(i, line)| Some((i + 1, line.split_once(" = ")?)))
+        .map(|(line, (op, value))| {
+            let instr = match op {
+                "mask" => {
+                    let mut xes: u64 = 0;
+                    let mut zeros: u64 = 0;
+                    let mut ones: u64 = 0;
+
+                    for (i, c) in value.chars().rev().enumerate() {
+                        match c {
+                            'X' => xes = set_bit(xes, i),
+                            '0' => zeros = set_bit(zeros, i),
+                            '1' => ones = set_bit(ones, i),
+                            _ => panic!("unknown mask character: {}", c),
+                        }
                     }
-                }
 
-                Instr::Mask { zeros, ones, xes }
-            }
+                    Instr::Mask(Mask { zeros, ones, xes })
+                }
 
-            mem => {
-                let (op, at) = mem.trim_end_matches(']').split_once('[').unwrap();
-                debug_assert_eq!(op, "mem");
+                mem => {
+                    let (op, at) = mem.trim_end_matches(']').split_once('[').unwrap();
+                    debug_assert_eq!(op, "mem");
 
-                Instr::Mem {
-                    at: at.parse().expect("failed to parse memory address"),
-                    value: value.parse().expect("failed to parse instruction argument"),
+                    Instr::Mem {
+                        at: at.parse().expect("failed to parse memory address"),
+                        value: value.parse().expect("failed to parse instruction argument"),
+                    }
                 }
-            }
+            };
+            (line, instr)
         })
 }
 
-pub fn part1(input: &str) -> u64 {
-    let mut mem = Memory::new();
+impl Decoder for V1 {
+    fn decode(&self, mask: &Mask, at: u64, value: u64) -> (Pattern, u64) {
+        (Pattern::new(at, 0), (value & !mask.zeros) | mask.ones)
+    }
+}
+
+impl Decoder for V2 {
+    fn decode(&self, mask: &Mask, at: u64, value: u64) -> (Pattern, u64) {
+        (Pattern::new(at | mask.ones, mask.xes), value)
+    }
+}
+
+impl<D: Decoder> BitmaskMachine<D> {
+    pub fn new(width: u32, decoder: D) -> Result<Self, MachineError> {
+        if width == 0 || width > 64 {
+            return Err(MachineError::InvalidWidth(width));
+        }
+
+        Ok(BitmaskMachine {
+            width,
+            decoder,
+            mask: Mask::default(),
+            mask_line: None,
+            memory: Memory::new(),
+            trace: None,
+        })
+    }
 
-    let mut and_mask = u64::MAX;
-    let mut or_mask = 0;
+    /// Record every memory write
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(vec![]);
+        self
+    }
+
+    pub fn execute(&mut self, instr: Instr) -> Result<(), MachineError> {
+        self.execute_line(None, instr)
+    }
 
-    for instr in parse(input) {
+    /// Execute an instruction from the given input line
+    pub fn execute_line(&mut self, line: Option<usize>, instr: Instr) -> Result<(), MachineError> {
+        let word = u64::MAX >> (64 - self.width);
+        let fits = |n: u64| n & !word == 0;
         match instr {
-            Instr::Mask { zeros, ones, .. } => {
-                and_mask = !zeros;
-                or_mask = ones;
+            Instr::Mask(mask) if !fits(mask.ones | mask.zeros | mask.xes) => {
+                Err(MachineError::MaskTooWide(mask))
+            }
+            Instr::Mask(mask) => {
+                self.mask = mask;
+                self.mask_line = line;
+                Ok(())
+            }
+            Instr::Mem { at, .. } if !fits(at) => Err(MachineError::AddressTooWide(at)),
+            Instr::Mem { value, .. } if !fits(value) => Err(MachineError::ValueTooWide(value)),
+            Instr::Mem { at, value } => {
+                let (pattern, value) = self.decoder.decode(&self.mask, at, value);
+                self.memory.put_pattern(pattern, value);
+                if let Some(trace) = &mut self.trace {
+                    trace.push(TracedWrite {
+                        mask_line: self.mask_line,
+                        pattern,
+                        value,
+                    });
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub fn run(&mut self, program: impl IntoIterator<Item = Instr>) -> Result<(), MachineError> {
+        program
+            .into_iter()
+            .try_for_each(|instr| self.execute(instr))
+    }
+
+    /// Run a program where every instruction is numbered with its input line
+    pub fn run_lines(
+        &mut self,
+        program: impl IntoIterator<Item = (usize, Instr)>,
+    ) -> Result<(), MachineError> {
+        program
+            .into_iter()
+            .try_for_each(|(line, instr)| self.execute_line(Some(line), instr))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    /// The writes made so far, or None if tracing is not enabled
+    pub fn trace(&self) -> Option<&[TracedWrite]> {
+        self.trace.as_deref()
+    }
+}
+
+impl TracedWrite {
+    /// Format the write like the instruction that would make it, along with the active mask
+    pub fn format(&self, width: u32) -> String {
+        let mask = match self.mask_line {
+            Some(line) => format!("mask from line {}", line),
+            None => "no mask".to_string(),
+        };
+        format!(
+            "mem[{}] = {} ({})",
+            self.pattern.format(width),
+            self.value,
+            mask
+        )
+    }
+}
+
+impl fmt::Display for MachineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MachineError::InvalidWidth(width) => {
+                write!(f, "word width must be between 1 and 64, was {}", width)
             }
-            Instr::Mem { at, value } => mem.put(at, (value & and_mask) | or_mask),
+            MachineError::MaskTooWide(mask) => write!(f, "mask is too wide: {:?}", mask),
+            MachineError::AddressTooWide(at) => write!(f, "address is too wide: {}", at),
+            MachineError::ValueTooWide(value) => write!(f, "value is too wide: {}", value),
         }
     }
+}
 
-    mem.sum()
+/// Run a program on a 36-bit machine
+fn run(input: &str, decoder: impl Decoder) -> u64 {
+    let mut machine = BitmaskMachine::new(36, decoder).unwrap();
+    machine
+        .run(parse(input))
+        .unwrap_or_else(|err| panic!("{}", err));
+    u64::try_from(machine.memory().sum()).expect("memory sum does not fit in a u64")
+}
+
+pub fn part1(input: &str) -> u64 {
+    run(input, V1)
 }
 
 #[inline(always)]
@@ -85,62 +422,259 @@ fn set_bit(val: u64, bit: usize) -> u64 {
     val | 1 << bit
 }
 
-#[inline(always)]
-fn unset_bit(val: u64, bit: usize) -> u64 {
-    val & !(1 << bit)
+pub fn part2(input: &str) -> u64 {
+    run(input, V2)
 }
 
-#[inline(always)]
-fn get_bit(val: u64, bit: usize) -> bool {
-    (val >> bit) & 1 == 1
-}
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse, parse_lines, part1, part2, BitmaskMachine, Decoder, Instr, MachineError, Mask,
+        Pattern, V1, V2,
+    };
+    use std::collections::HashMap;
 
-pub fn part2(input: &str) -> u64 {
-    let mut mem = Memory::new();
+    #[test]
+    pub fn test_part1() {
+        let input = include_str!("test-input");
+        assert_eq!(part1(input), 165);
+    }
 
-    let mut or_mask = 0;
-    let mut float_mask = 0;
+    #[test]
+    pub fn test_part2() {
+        let input = include_str!("test-input2");
+        assert_eq!(part2(input), 208);
+    }
 
-    for instr in parse(input) {
-        match instr {
-            Instr::Mask { xes, ones, .. } => {
-                float_mask = xes;
-                or_mask = ones;
-            }
-            Instr::Mem { at, value } => {
-                fn set_floating(at: u64, i: usize, float_mask: u64, value: u64, mem: &mut Memory) {
-                    for f in i..BITS {
-                        if get_bit(float_mask, f) {
-                            set_floating(set_bit(at, f), f + 1, float_mask, value, mem);
-                            set_floating(unset_bit(at, f), f + 1, float_mask, value, mem);
-                            return;
+    #[test]
+    pub fn test_part2_floating() {
+        // 34 floating bits, which would be 17 billion addresses if expanded
+        let input = include_str!("test-input");
+        assert_eq!(part2(input), 101 << 34);
+    }
+
+    #[test]
+    pub fn test_subtract() {
+        let mut out = vec![];
+
+        // 0XX minus 01X leaves 00X
+        Pattern::new(0b000, 0b011).subtract(&Pattern::new(0b010, 0b001), &mut out);
+        assert_eq!(out, vec![Pattern::new(0b000, 0b001)]);
+        out.clear();
+
+        // XXX minus 1X0 leaves XX1 and 0X0
+        Pattern::new(0, 0b111).subtract(&Pattern::new(0b100, 0b010), &mut out);
+        assert_eq!(
+            out,
+            vec![Pattern::new(0b001, 0b110), Pattern::new(0b000, 0b010)]
+        );
+        out.clear();
+
+        // disjoint patterns are left alone
+        Pattern::new(0b100, 0b011).subtract(&Pattern::new(0b000, 0b011), &mut out);
+        assert_eq!(out, vec![Pattern::new(0b100, 0b011)]);
+        out.clear();
+
+        // a pattern minus a superset of itself leaves nothing
+        Pattern::new(0b101, 0b000).subtract(&Pattern::new(0b000, 0b111), &mut out);
+        assert_eq!(out, vec![]);
+    }
+
+    /// A xorshift generator, so that the random programs are the same on every run
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+    }
+
+    /// Run a program by expanding every floating address, the way the puzzle describes it
+    fn run_expanded(program: &[Instr], version: u32) -> HashMap<u64, u64> {
+        let mut memory = HashMap::new();
+        let mut mask = Mask::default();
+        for &instr in program {
+            match instr {
+                Instr::Mask(m) => mask = m,
+                Instr::Mem { at, value } if version == 1 => {
+                    memory.insert(at, value & !mask.zeros | mask.ones);
+                }
+                Instr::Mem { at, value } => {
+                    // every subset of the floating bits
+                    let mut xes = 0u64;
+                    loop {
+                        memory.insert((at | mask.ones) & !mask.xes | xes, value);
+                        xes = xes.wrapping_sub(mask.xes) & mask.xes;
+                        if xes == 0 {
+                            break;
                         }
                     }
+                }
+            }
+        }
+        memory
+    }
 
-                    mem.put(at, value);
+    #[test]
+    pub fn test_random_programs() {
+        const WIDTH: u32 = 8;
+        let mut rng = Rng(0x2020_1214);
+
+        for _ in 0..200 {
+            let mut program = vec![];
+            for _ in 0..1 + rng.below(40) {
+                if rng.below(4) == 0 {
+                    let mut mask = Mask::default();
+                    for bit in 0..WIDTH {
+                        match rng.below(3) {
+                            0 => mask.zeros |= 1 << bit,
+                            1 => mask.ones |= 1 << bit,
+                            _ => mask.xes |= 1 << bit,
+                        }
+                    }
+                    program.push(Instr::Mask(mask));
+                } else {
+                    let at = rng.below(1 << WIDTH);
+                    let value = rng.below(1 << WIDTH);
+                    program.push(Instr::Mem { at, value });
                 }
+            }
+
+            let mut v1 = BitmaskMachine::new(WIDTH, V1).unwrap();
+            v1.run(program.iter().copied()).unwrap();
+            let mut v2 = BitmaskMachine::new(WIDTH, V2).unwrap();
+            v2.run(program.iter().copied()).unwrap();
 
-                set_floating(at | or_mask, 0, float_mask, value, &mut mem);
+            for (machine, expected) in [
+                (v1.memory(), run_expanded(&program, 1)),
+                (v2.memory(), run_expanded(&program, 2)),
+            ] {
+                for at in 0..1 << WIDTH {
+                    assert_eq!(machine.get(at), expected.get(&at).copied(), "{:?}", program);
+                }
+                assert_eq!(machine.sum(), expected.values().map(|&v| v as u128).sum());
             }
         }
     }
 
-    mem.sum()
-}
+    #[test]
+    pub fn test_width() {
+        assert!(BitmaskMachine::new(0, V1).is_err());
+        assert!(BitmaskMachine::new(65, V1).is_err());
 
-#[cfg(test)]
-mod tests {
-    use super::{part1, part2};
+        let mut machine = BitmaskMachine::new(64, V1).unwrap();
+        assert_eq!(
+            machine.execute(Instr::Mem {
+                at: 0,
+                value: u64::MAX
+            }),
+            Ok(())
+        );
+
+        // every one of the 2^64 addresses holding the largest value
+        let mut machine = BitmaskMachine::new(64, V2).unwrap();
+        let program = format!("mask = {}\nmem[0] = {}\n", "X".repeat(64), u64::MAX);
+        machine.run(parse(&program)).unwrap();
+        assert_eq!(machine.memory().sum(), u64::MAX as u128 * (1 << 64));
+
+        let mut machine = BitmaskMachine::new(8, V1).unwrap();
+        let mem = |at, value| Instr::Mem { at, value };
+        assert_eq!(machine.execute(mem(255, 255)), Ok(()));
+        assert_eq!(
+            machine.execute(mem(256, 1)),
+            Err(MachineError::AddressTooWide(256))
+        );
+        assert_eq!(
+            machine.execute(mem(1, 256)),
+            Err(MachineError::ValueTooWide(256))
+        );
+
+        let program = "mask = 1X0\nmem[3] = 7\nmask = 000000000\n";
+        assert_eq!(
+            machine.run(parse(program)),
+            Err(MachineError::MaskTooWide(Mask {
+                ones: 0,
+                zeros: 0b111111111,
+                xes: 0,
+            }))
+        );
+        assert_eq!(machine.memory().sum(), 255 + 0b110);
+    }
+
+    /// A decoder that ignores the mask entirely
+    struct Unmasked;
+
+    impl Decoder for Unmasked {
+        fn decode(&self, _mask: &Mask, at: u64, value: u64) -> (Pattern, u64) {
+            (Pattern::new(at, 0), value)
+        }
+    }
 
     #[test]
-    pub fn test_part1() {
+    pub fn test_custom_decoder() {
         let input = include_str!("test-input");
-        assert_eq!(part1(input), 165);
+        let mut machine = BitmaskMachine::new(36, Unmasked).unwrap();
+        machine.run(parse(input)).unwrap();
+        assert_eq!(machine.memory().sum(), 101);
     }
 
     #[test]
-    pub fn test_part2() {
+    pub fn test_trace_v1() {
+        let input = include_str!("test-input");
+        let mut machine = BitmaskMachine::new(36, V1).unwrap().with_trace();
+        machine.run_lines(parse_lines(input)).unwrap();
+
+        let trace: Vec<String> = machine
+            .trace()
+            .unwrap()
+            .iter()
+            .map(|write| write.format(machine.width()))
+            .collect();
+        assert_eq!(
+            trace,
+            vec![
+                "mem[8] = 73 (mask from line 1)",
+                "mem[7] = 101 (mask from line 1)",
+                "mem[8] = 64 (mask from line 1)",
+            ]
+        );
+        assert_eq!(machine.memory().dump(36), "mem[7] = 101\nmem[8] = 64\n");
+    }
+
+    #[test]
+    pub fn test_trace_v2() {
         let input = include_str!("test-input2");
-        assert_eq!(part2(input), 208);
+        let mut machine = BitmaskMachine::new(36, V2).unwrap().with_trace();
+        machine.run_lines(parse_lines(input)).unwrap();
+
+        let trace: Vec<String> = machine
+            .trace()
+            .unwrap()
+            .iter()
+            .map(|write| write.format(6))
+            .collect();
+        assert_eq!(
+            trace,
+            vec![
+                "mem[X1101X] = 100 (mask from line 1)",
+                "mem[01X0XX] = 1 (mask from line 3)",
+            ]
+        );
+
+        // the second write overwrites 26 and 27 from the first one
+        assert_eq!(
+            machine.memory().dump(6),
+            "mem[01X0XX] = 1\nmem[11101X] = 100\n"
+        );
+    }
+
+    #[test]
+    pub fn test_trace_disabled() {
+        let mut machine = BitmaskMachine::new(36, V1).unwrap();
+        machine.run(parse(include_str!("test-input"))).unwrap();
+        assert_eq!(machine.trace(), None);
     }
 }
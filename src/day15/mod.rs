@@ -5,69 +5,130 @@ pub fn parse(input: &str) -> Vec<usize> {
         .map(|num| num.parse().expect("failed to parse number"))
         .collect()
 }
-struct VecMap<T> {
-    buffer: Vec<Option<T>>,
+
+/// The numbers spoken in the elves' memory game, one per turn
+///
+/// After the starting numbers, every turn speaks how many turns apart the previous number was
+/// last spoken, or 0 if it was new. Starting from nothing gives Van Eck's sequence.
+pub struct MemoryGame<M = TurnTable> {
+    starting_numbers: Vec<usize>,
+
+    /// The current turn, starting at 0
+    turn: usize,
+
+    /// The number to speak this turn, once the starting numbers have run out
+    next: usize,
+
+    /// The last turn each number was spoken
+    spoken: M,
+}
+
+/// Storage for the last turn each number was spoken
+pub trait LastSpoken {
+    /// Record that `num` was spoken on `turn`, and return the turn it was previously spoken
+    fn speak(&mut self, num: usize, turn: usize) -> Option<usize>;
+
+    /// The number of bytes allocated by the storage
+    fn allocated(&self) -> usize;
+}
+
+/// The last turn each number was spoken, indexed by number
+///
+/// Every number spoken after the starting numbers is smaller than the turn count, so the table
+/// can be allocated up front. Turns are stored as `u32`, with [NEVER] marking numbers that have
+/// not been spoken.
+pub struct TurnTable {
+    turns: Vec<u32>,
 }
 
-#[allow(dead_code)]
-impl<T: Copy> VecMap<T> {
-    pub fn with_capacity(cap: usize) -> Self {
-        Self {
-            buffer: Vec::with_capacity(cap),
+const NEVER: u32 = u32::MAX;
+
+impl TurnTable {
+    /// A table with room for the numbers `0..len`. It grows if a larger number is spoken.
+    pub fn with_len(len: usize) -> Self {
+        TurnTable {
+            turns: vec![NEVER; len],
         }
     }
+}
 
-    pub fn get(&self, index: usize) -> Option<T> {
-        if index >= self.buffer.len() {
-            return None;
+impl LastSpoken for TurnTable {
+    fn speak(&mut self, num: usize, turn: usize) -> Option<usize> {
+        if num >= self.turns.len() {
+            self.turns.resize(num + 1, NEVER);
         }
-        self.buffer[index]
-    }
 
-    pub fn insert(&mut self, index: usize, value: T) -> Option<T> {
-        if index >= self.buffer.len() {
-            self.buffer.resize_with(index + 1, || None);
+        let turn = u32::try_from(turn)
+            .ok()
+            .filter(|&turn| turn != NEVER)
+            .expect("too many turns for a TurnTable");
+        match std::mem::replace(&mut self.turns[num], turn) {
+            NEVER => None,
+            last => Some(last as usize),
         }
+    }
 
-        let old = self.buffer[index];
-        self.buffer[index] = Some(value);
-        old
+    fn allocated(&self) -> usize {
+        self.turns.capacity() * std::mem::size_of::<u32>()
     }
 }
 
-fn nth_number_spoken(input: &str, n: usize) -> usize {
-    let starting_numbers = parse(input);
+impl MemoryGame {
+    pub fn new(starting_numbers: &[usize]) -> Self {
+        Self::with_turns(starting_numbers, 0)
+    }
 
-    let mut spoken = VecMap::with_capacity(65536);
+    /// A game with storage allocated for playing `turns` turns
+    pub fn with_turns(starting_numbers: &[usize], turns: usize) -> Self {
+        let len = table_len(starting_numbers, turns);
+        Self::with_storage(starting_numbers, TurnTable::with_len(len))
+    }
+}
 
-    let mut most_recently_spoken = 0;
-    let mut first_time = true;
-    let mut previous_age = 0;
+/// The length of the [TurnTable] needed to play `turns` turns without growing it
+fn table_len(starting_numbers: &[usize], turns: usize) -> usize {
+    let largest = starting_numbers.iter().map(|&num| num + 1).max();
+    largest.unwrap_or(0).max(turns)
+}
+
+impl<M: LastSpoken> MemoryGame<M> {
+    pub fn with_storage(starting_numbers: &[usize], spoken: M) -> Self {
+        MemoryGame {
+            starting_numbers: starting_numbers.to_vec(),
+            turn: 0,
+            next: 0,
+            spoken,
+        }
+    }
 
-    for (i, &num) in starting_numbers.iter().enumerate() {
-        spoken.insert(num, i);
-        most_recently_spoken = num;
+    pub fn storage(&self) -> &M {
+        &self.spoken
     }
+}
+
+impl<M: LastSpoken> Iterator for MemoryGame<M> {
+    type Item = usize;
 
-    for i in starting_numbers.len()..n {
-        // if the previous number had not been spoken before
-        let speak = if first_time {
-            0 // speak 0
-        } else {
-            // else speak the age of the number
-            previous_age
+    fn next(&mut self) -> Option<usize> {
+        let speak = match self.starting_numbers.get(self.turn) {
+            Some(&num) => num,
+            None => self.next,
         };
 
-        if let Some(spoken_at) = spoken.insert(speak, i) {
-            first_time = false;
-            previous_age = (i - spoken_at) as usize;
-        } else {
-            first_time = true;
-        }
-        most_recently_spoken = speak;
+        self.next = match self.spoken.speak(speak, self.turn) {
+            Some(spoken_at) => self.turn - spoken_at,
+            None => 0,
+        };
+        self.turn += 1;
+
+        Some(speak)
     }
+}
 
-    most_recently_spoken
+fn nth_number_spoken(input: &str, n: usize) -> usize {
+    MemoryGame::with_turns(&parse(input), n)
+        .nth(n - 1)
+        .expect("the game never ends")
 }
 
 pub fn part1(input: &str) -> usize {
@@ -80,7 +141,41 @@ pub fn part2(input: &str) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, table_len, LastSpoken, MemoryGame};
+    use test::Bencher;
+
+    /// The storage the game used before [super::TurnTable], kept to compare against
+    struct VecMap<T> {
+        buffer: Vec<Option<T>>,
+    }
+
+    impl<T: Copy> VecMap<T> {
+        pub fn with_capacity(cap: usize) -> Self {
+            Self {
+                buffer: Vec::with_capacity(cap),
+            }
+        }
+
+        pub fn insert(&mut self, index: usize, value: T) -> Option<T> {
+            if index >= self.buffer.len() {
+                self.buffer.resize_with(index + 1, || None);
+            }
+
+            let old = self.buffer[index];
+            self.buffer[index] = Some(value);
+            old
+        }
+    }
+
+    impl LastSpoken for VecMap<usize> {
+        fn speak(&mut self, num: usize, turn: usize) -> Option<usize> {
+            self.insert(num, turn)
+        }
+
+        fn allocated(&self) -> usize {
+            self.buffer.capacity() * std::mem::size_of::<Option<usize>>()
+        }
+    }
 
     const TEST_DATA: &[(&str, usize)] = &[
         ("0,3,6", 436),
@@ -104,4 +199,86 @@ mod tests {
         let input = include_str!("test-input");
         assert_eq!(part2(input), 1337);
     }
+
+    #[test]
+    pub fn test_memory_game() {
+        let first: Vec<usize> = MemoryGame::new(&[0, 3, 6]).take(10).collect();
+        assert_eq!(first, vec![0, 3, 6, 0, 3, 3, 1, 0, 4, 0]);
+
+        let turn = MemoryGame::new(&[0, 3, 6]).position(|num| num == 4);
+        assert_eq!(turn, Some(8));
+
+        // with no starting numbers, it's Van Eck's sequence
+        let van_eck: Vec<usize> = MemoryGame::new(&[]).take(10).collect();
+        assert_eq!(van_eck, vec![0, 0, 1, 0, 2, 0, 2, 2, 1, 6]);
+    }
+
+    #[test]
+    pub fn test_storage() {
+        for &(input, output) in TEST_DATA {
+            let numbers = super::parse(input);
+            let mut table = MemoryGame::with_turns(&numbers, 2020);
+            let mut vec_map = MemoryGame::with_storage(&numbers, VecMap::with_capacity(0));
+            assert!(
+                table
+                    .by_ref()
+                    .zip(vec_map.by_ref())
+                    .take(2020)
+                    .all(|(a, b)| a == b),
+                "input was: {}",
+                input
+            );
+
+            // the table was allocated up front, and stores 4 bytes per number instead of 16
+            assert_eq!(table.storage().allocated(), 2020 * 4);
+            assert!(table.storage().allocated() * 4 <= vec_map.storage().allocated());
+            assert_eq!(MemoryGame::new(&numbers).nth(2019), Some(output));
+        }
+    }
+
+    #[test]
+    pub fn test_allocated_part2() {
+        // every number spoken is smaller than the turn count, so the table never grows past this
+        let len = table_len(&[0, 3, 6], PART2_TURNS);
+        assert_eq!(len * std::mem::size_of::<u32>(), 120_000_000);
+    }
+
+    const BENCH_TURNS: usize = 1_000_000;
+    const PART2_TURNS: usize = 30_000_000;
+
+    /// Play a full part 2 game with both storages, and print how much memory they end up with
+    ///
+    /// Run it with `cargo test --release -- --ignored --nocapture test_report_allocated`.
+    #[test]
+    #[ignore]
+    pub fn test_report_allocated() {
+        fn report<M: LastSpoken>(name: &str, mut game: MemoryGame<M>) {
+            game.nth(PART2_TURNS - 1);
+            let allocated = game.storage().allocated();
+            println!(
+                "{} after {} turns: {} MB",
+                name,
+                PART2_TURNS,
+                allocated / 1_000_000
+            );
+        }
+
+        report("TurnTable", MemoryGame::with_turns(&[0, 3, 6], PART2_TURNS));
+        report(
+            "VecMap",
+            MemoryGame::with_storage(&[0, 3, 6], VecMap::with_capacity(65536)),
+        );
+    }
+
+    #[bench]
+    pub fn bench_turn_table(b: &mut Bencher) {
+        b.iter(|| MemoryGame::with_turns(&[0, 3, 6], BENCH_TURNS).nth(BENCH_TURNS - 1));
+    }
+
+    #[bench]
+    pub fn bench_vec_map(b: &mut Bencher) {
+        b.iter(|| {
+            MemoryGame::with_storage(&[0, 3, 6], VecMap::with_capacity(65536)).nth(BENCH_TURNS - 1)
+        });
+    }
 }
@@ -0,0 +1,230 @@
+//! Work out which field each ticket column holds, one nearby ticket at a time
+
+use super::{assign_fields, unique_assignment, AssignError, FieldRanges};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Narrows down the possible fields of every column as nearby tickets arrive
+pub struct TicketDecoder<'a, 'r> {
+    field_ranges: &'r FieldRanges<'a>,
+
+    /// The fields that every column could still hold
+    possibilities: Vec<HashSet<&'a str>>,
+
+    /// The number of valid tickets added so far
+    tickets: usize,
+
+    /// The field of every column, once it's been determined
+    resolved: Option<Vec<&'a str>>,
+
+    /// The number of tickets it took to determine the fields, as long as they are determined
+    resolved_after: Option<usize>,
+}
+
+/// A ticket where every value is labelled with its field
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedTicket<'a> {
+    /// The field and value of every column, in order
+    fields: Vec<(&'a str, u64)>,
+}
+
+impl<'a, 'r> TicketDecoder<'a, 'r> {
+    pub fn new(field_ranges: &'r FieldRanges<'a>, columns: usize) -> Self {
+        let mut decoder = TicketDecoder {
+            field_ranges,
+            possibilities: vec![field_ranges.inner.keys().copied().collect(); columns],
+            tickets: 0,
+            resolved: None,
+            resolved_after: None,
+        };
+
+        // with a single column and field, there is nothing to narrow down
+        decoder.update();
+        decoder
+    }
+
+    /// Narrow down the possible fields using a nearby ticket
+    ///
+    /// Tickets with the wrong number of values, or with values that are not valid for any field,
+    /// are ignored. Returns whether the ticket was used.
+    pub fn add(&mut self, ticket: &[u64]) -> bool {
+        if ticket.len() != self.possibilities.len() {
+            return false;
+        }
+
+        let valid_for: Vec<HashSet<&str>> = ticket
+            .iter()
+            .map(|&value| self.field_ranges.number_valid_for(value).collect())
+            .collect();
+        if valid_for.iter().any(|fields| fields.is_empty()) {
+            return false;
+        }
+
+        let mut changed = false;
+        for (possible, valid) in self.possibilities.iter_mut().zip(&valid_for) {
+            let before = possible.len();
+            possible.retain(|field| valid.contains(field));
+            changed |= possible.len() != before;
+        }
+
+        self.tickets += 1;
+        if changed {
+            self.update();
+        }
+        true
+    }
+
+    fn update(&mut self) {
+        // only check for a unique assignment here, the reason there is none is left to resolve
+        self.resolved = unique_assignment(&self.possibilities);
+
+        // a later ticket can rule out the only assignment there was
+        self.resolved_after = match self.resolved {
+            Some(_) => self.resolved_after.or(Some(self.tickets)),
+            None => None,
+        };
+    }
+
+    /// The fields that every column could still hold
+    pub fn possibilities(&self) -> &[HashSet<&'a str>] {
+        &self.possibilities
+    }
+
+    /// The number of valid tickets added so far
+    pub fn tickets(&self) -> usize {
+        self.tickets
+    }
+
+    /// The field of every column, if the tickets so far determine it
+    pub fn resolved(&self) -> Option<&[&'a str]> {
+        self.resolved.as_deref()
+    }
+
+    /// How many valid tickets it took before the fields were determined
+    ///
+    /// Like [resolved](Self::resolved), this is `None` again if a later ticket leaves no valid
+    /// assignment.
+    pub fn resolved_after(&self) -> Option<usize> {
+        self.resolved_after
+    }
+
+    /// The field of every column, or why it could not be determined
+    pub fn resolve(&self) -> Result<Vec<&'a str>, AssignError<'a>> {
+        match &self.resolved {
+            Some(fields) => Ok(fields.clone()),
+            None => assign_fields(&self.possibilities),
+        }
+    }
+
+    /// Label the values of a ticket with their fields
+    pub fn decode(&self, ticket: &[u64]) -> Result<DecodedTicket<'a>, AssignError<'a>> {
+        Ok(DecodedTicket::new(&self.resolve()?, ticket))
+    }
+}
+
+impl<'a> DecodedTicket<'a> {
+    pub fn new(field_names: &[&'a str], ticket: &[u64]) -> Self {
+        DecodedTicket {
+            fields: field_names
+                .iter()
+                .copied()
+                .zip(ticket.iter().copied())
+                .collect(),
+        }
+    }
+
+    /// The value of a field
+    pub fn get(&self, name: &str) -> Option<u64> {
+        self.fields
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|&(_, value)| value)
+    }
+
+    /// Every field and its value, in column order
+    pub fn fields(&self) -> impl Iterator<Item = (&'a str, u64)> + '_ {
+        self.fields.iter().copied()
+    }
+
+    /// The product of the values of every field whose name matches the predicate
+    pub fn product(&self, predicate: impl Fn(&str) -> bool) -> u64 {
+        self.fields
+            .iter()
+            .filter(|(name, _)| predicate(name))
+            .map(|&(_, value)| value)
+            .product()
+    }
+}
+
+impl fmt::Display for DecodedTicket<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.fields {
+            writeln!(f, "{}: {}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{parse, AssignError};
+    use super::{DecodedTicket, TicketDecoder};
+
+    #[test]
+    pub fn test_decoder() {
+        let input = parse(include_str!("test-input2"));
+        let mut decoder = TicketDecoder::new(&input.field_ranges, input.my_ticket.len());
+
+        let mut tickets = input.nearby_tickets.iter();
+        assert!(decoder.add(tickets.next().unwrap()));
+        assert!(decoder.add(tickets.next().unwrap()));
+        assert_eq!(decoder.resolved(), None);
+        assert!(matches!(decoder.resolve(), Err(AssignError::Ambiguous(_))));
+
+        assert!(decoder.add(tickets.next().unwrap()));
+        assert_eq!(decoder.resolved(), Some(&["row", "class", "seat"][..]));
+        assert_eq!(decoder.resolved_after(), Some(3));
+
+        // 15 is not a valid seat, so the seat column has no field left
+        assert!(decoder.add(&[15, 15, 15]));
+        assert_eq!(decoder.resolved(), None);
+        assert_eq!(decoder.resolved_after(), None);
+        assert!(decoder.resolve().is_err());
+    }
+
+    #[test]
+    pub fn test_decoder_invalid() {
+        let input = parse(include_str!("test-input"));
+        let mut decoder = TicketDecoder::new(&input.field_ranges, input.my_ticket.len());
+
+        let used: Vec<bool> = input
+            .nearby_tickets
+            .iter()
+            .map(|ticket| decoder.add(ticket))
+            .collect();
+        assert_eq!(used, vec![true, false, false, false]);
+        assert!(!decoder.add(&[7, 3]));
+        assert_eq!(decoder.tickets(), 1);
+    }
+
+    #[test]
+    pub fn test_decoded_ticket() {
+        let input = parse(include_str!("test-input2"));
+        let mut decoder = TicketDecoder::new(&input.field_ranges, input.my_ticket.len());
+        for ticket in &input.nearby_tickets {
+            decoder.add(ticket);
+        }
+
+        let ticket = decoder.decode(&input.my_ticket).unwrap();
+        assert_eq!(
+            ticket,
+            DecodedTicket::new(&["row", "class", "seat"], &[11, 12, 13])
+        );
+        assert_eq!(ticket.get("class"), Some(12));
+        assert_eq!(ticket.get("departure"), None);
+        assert_eq!(ticket.product(|name| name.starts_with('s')), 13);
+        assert_eq!(ticket.product(|name| name != "row"), 12 * 13);
+        assert_eq!(ticket.product(|_| false), 1);
+        assert_eq!(ticket.to_string(), "row: 11\nclass: 12\nseat: 13\n");
+    }
+}
@@ -0,0 +1,183 @@
+//! Bipartite matching between ticket columns and fields
+
+/// Try to give `column` a field, moving other columns to different fields if needed
+///
+/// `column_of[field]` is the column currently holding each field.
+fn augment(
+    column: usize,
+    candidates: &[Vec<usize>],
+    column_of: &mut [Option<usize>],
+    visited: &mut [bool],
+) -> bool {
+    for &field in &candidates[column] {
+        if visited[field] {
+            continue;
+        }
+        visited[field] = true;
+
+        let free = match column_of[field] {
+            None => true,
+            Some(other) => augment(other, candidates, column_of, visited),
+        };
+
+        if free {
+            column_of[field] = Some(column);
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Give every column a distinct field out of its candidates
+///
+/// `candidates[column]` lists the fields that the column may hold, as indices below `fields`.
+/// Returns the field of every column, or the columns that could not be given a field.
+pub fn perfect_matching(
+    candidates: &[Vec<usize>],
+    fields: usize,
+) -> Result<Vec<usize>, Vec<usize>> {
+    let mut column_of = vec![None; fields];
+    let mut unmatched = vec![];
+    for column in 0..candidates.len() {
+        let mut visited = vec![false; fields];
+        if !augment(column, candidates, &mut column_of, &mut visited) {
+            unmatched.push(column);
+        }
+    }
+
+    if !unmatched.is_empty() {
+        return Err(unmatched);
+    }
+
+    let mut field_of = vec![0; candidates.len()];
+    for (field, column) in column_of.into_iter().enumerate() {
+        if let Some(column) = column {
+            field_of[column] = field;
+        }
+    }
+    Ok(field_of)
+}
+
+/// Whether `field_of` is the only perfect matching
+///
+/// Another matching exists if some column can move to a field that no column holds, or if the
+/// columns can swap fields in a cycle.
+pub fn is_unique(candidates: &[Vec<usize>], fields: usize, field_of: &[usize]) -> bool {
+    let mut column_of = vec![None; fields];
+    for (column, &field) in field_of.iter().enumerate() {
+        column_of[field] = Some(column);
+    }
+
+    // column -> the columns holding the other fields it could take
+    let mut edges = Vec::with_capacity(candidates.len());
+    for (column, fields) in candidates.iter().enumerate() {
+        let mut others = vec![];
+        for &field in fields.iter().filter(|&&f| f != field_of[column]) {
+            match column_of[field] {
+                None => return false,
+                Some(other) => others.push(other),
+            }
+        }
+        edges.push(others);
+    }
+
+    // look for a cycle with a depth-first search
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        New,
+        Active,
+        Done,
+    }
+
+    fn has_cycle(column: usize, edges: &[Vec<usize>], state: &mut [State]) -> bool {
+        state[column] = State::Active;
+        for &next in &edges[column] {
+            let found = match state[next] {
+                State::Active => true,
+                State::New => has_cycle(next, edges, state),
+                State::Done => false,
+            };
+            if found {
+                return true;
+            }
+        }
+        state[column] = State::Done;
+        false
+    }
+
+    let mut state = vec![State::New; candidates.len()];
+    (0..candidates.len())
+        .all(|column| state[column] != State::New || !has_cycle(column, &edges, &mut state))
+}
+
+/// For every column, the fields it holds in at least one perfect matching
+///
+/// The matching is unique exactly when every column has a single alternative.
+pub fn alternatives(candidates: &[Vec<usize>], fields: usize) -> Vec<Vec<usize>> {
+    let mut forced = candidates.to_vec();
+    (0..candidates.len())
+        .map(|column| {
+            let possible = candidates[column]
+                .iter()
+                .copied()
+                .filter(|&field| {
+                    forced[column] = vec![field];
+                    perfect_matching(&forced, fields).is_ok()
+                })
+                .collect();
+            forced[column] = candidates[column].clone();
+            possible
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{alternatives, is_unique, perfect_matching};
+
+    #[test]
+    pub fn test_perfect_matching() {
+        // the first column has to give up field 0 for the second one
+        let candidates = vec![vec![0, 1], vec![0]];
+        assert_eq!(perfect_matching(&candidates, 2), Ok(vec![1, 0]));
+
+        // three columns fighting over two fields
+        let candidates = vec![vec![0, 1], vec![0], vec![1]];
+        assert_eq!(perfect_matching(&candidates, 2), Err(vec![2]));
+
+        // there may be more fields than columns
+        let candidates = vec![vec![2], vec![0, 2]];
+        assert_eq!(perfect_matching(&candidates, 3), Ok(vec![2, 0]));
+    }
+
+    #[test]
+    pub fn test_alternatives() {
+        let candidates = vec![vec![0, 1, 2], vec![0, 1], vec![0]];
+        assert_eq!(
+            alternatives(&candidates, 3),
+            vec![vec![2], vec![1], vec![0]]
+        );
+
+        // the first two columns can swap fields
+        let candidates = vec![vec![0, 1], vec![0, 1], vec![0, 1, 2]];
+        assert_eq!(
+            alternatives(&candidates, 3),
+            vec![vec![0, 1], vec![0, 1], vec![2]]
+        );
+    }
+
+    #[test]
+    pub fn test_is_unique() {
+        let candidates = vec![vec![0, 1, 2], vec![0, 1], vec![0]];
+        assert!(is_unique(&candidates, 3, &[2, 1, 0]));
+
+        // the first two columns can swap fields
+        let candidates = vec![vec![0, 1], vec![0, 1], vec![0, 1, 2]];
+        assert!(!is_unique(&candidates, 3, &[0, 1, 2]));
+
+        // the second column could move to the unused field 2
+        let candidates = vec![vec![0], vec![1, 2]];
+        assert!(!is_unique(&candidates, 3, &[0, 1]));
+    }
+}
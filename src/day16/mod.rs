@@ -1,70 +1,200 @@
+pub mod decoder;
+pub mod matching;
+pub mod report;
+
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::ops::RangeInclusive;
 
 pub type Range = RangeInclusive<u64>;
 
+/// A set of numbers, stored as sorted and disjoint intervals
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Ranges {
+    intervals: Vec<Range>,
+}
+
 #[derive(Debug)]
 pub struct FieldRanges<'a> {
-    inner: HashMap<&'a str, (Range, Range)>,
+    inner: HashMap<&'a str, Ranges>,
 }
 
 #[derive(Debug)]
 pub struct Input<'a> {
     field_ranges: FieldRanges<'a>,
     nearby_tickets: Vec<Vec<u64>>,
+
+    /// The input line of every nearby ticket
+    nearby_lines: Vec<usize>,
+
     my_ticket: Vec<u64>,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AssignError<'a> {
+    /// No assignment gives these columns a field
+    Unsolvable(Vec<usize>),
+
+    /// More than one assignment is valid. Lists every column that could hold more than one field,
+    /// along with those fields.
+    Ambiguous(Vec<(usize, Vec<&'a str>)>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The line of the error, starting at 1
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A rule that is not on the form `name: ranges`
+    MalformedRule(String),
+    InvalidRange(String),
+    DuplicateField(String),
+    InvalidNumber(String),
+    MissingSection(&'static str),
+}
+
 pub fn parse(input: &str) -> Input {
-    let mut sections = input.split("\n\n");
-    let field_ranges_input = sections.next().unwrap();
-    let my_ticket_input = sections.next().unwrap();
-    let nearby_tickets_input = sections.next().unwrap();
-
-    // oh holy lords of parsing, please have mercy on my soul
-
-    fn to_range(input: &str) -> Range {
-        let (from, to) = input.split_once("-").unwrap();
-        let from = from.parse().unwrap();
-        let to = to.parse().unwrap();
-        Range::new(from, to)
-    }
-
-    let field_ranges = field_ranges_input
-        .lines()
-        .filter_map(|line| line.split_once(": "))
-        .filter_map(|(name, ranges)| {
-            ranges
-                .split_once(" or ")
-                .map(|(r1, r2)| (name, (to_range(r1), to_range(r2))))
+    parse_strict(input).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Parse a range on the form `from-to`
+fn parse_range(input: &str) -> Option<Range> {
+    let (from, to) = input.split_once('-')?;
+    let from = from.parse().ok()?;
+    let to = to.parse().ok()?;
+    (from <= to).then_some(Range::new(from, to))
+}
+
+/// Parse a rule on the form `name: 1-3 or 5-7`, with any number of ranges
+fn parse_rule(line: &str) -> Result<(&str, Ranges), ParseErrorKind> {
+    let (name, ranges) = line
+        .split_once(": ")
+        .ok_or_else(|| ParseErrorKind::MalformedRule(line.to_string()))?;
+
+    let ranges = ranges
+        .split(" or ")
+        .map(|range| parse_range(range).ok_or_else(|| ParseErrorKind::InvalidRange(range.into())))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((name, Ranges::new(ranges)))
+}
+
+fn parse_ticket(line: &str) -> Result<Vec<u64>, ParseErrorKind> {
+    line.split(',')
+        .map(|field| {
+            field
+                .parse()
+                .map_err(|_| ParseErrorKind::InvalidNumber(field.to_string()))
         })
-        .collect();
+        .collect()
+}
+
+pub fn parse_strict(input: &str) -> Result<Input<'_>, ParseError> {
+    let mut lines = input.lines().enumerate().map(|(i, line)| (i + 1, line));
+    let error = |line, kind| ParseError { line, kind };
+
+    let mut field_ranges = HashMap::new();
+    for (line, text) in lines.by_ref().take_while(|(_, text)| !text.is_empty()) {
+        let (name, ranges) = parse_rule(text).map_err(|kind| error(line, kind))?;
+        if field_ranges.insert(name, ranges).is_some() {
+            return Err(error(
+                line,
+                ParseErrorKind::DuplicateField(name.to_string()),
+            ));
+        }
+    }
 
-    let my_ticket = my_ticket_input
-        .lines()
-        .skip(1)
+    let mut section = |header: &'static str| {
+        let (line, text) = lines
+            .by_ref()
+            .find(|(_, text)| !text.is_empty())
+            .ok_or(error(
+                input.lines().count() + 1,
+                ParseErrorKind::MissingSection(header),
+            ))?;
+        if text != header {
+            return Err(error(line, ParseErrorKind::MissingSection(header)));
+        }
+
+        lines
+            .by_ref()
+            .take_while(|(_, text)| !text.is_empty())
+            .map(|(line, text)| match parse_ticket(text) {
+                Ok(ticket) => Ok((line, ticket)),
+                Err(kind) => Err(error(line, kind)),
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    let my_ticket = section("your ticket:")?
+        .into_iter()
         .next()
-        .iter()
-        .flat_map(|line| line.split(','))
-        .map(|field| field.parse().unwrap())
-        .collect();
-
-    let nearby_tickets = nearby_tickets_input
-        .lines()
-        .skip(1)
-        .map(|line| {
-            line.split(',')
-                .map(|field| field.parse().unwrap())
-                .collect()
-        })
-        .collect();
+        .map(|(_, ticket)| ticket)
+        .unwrap_or_default();
+    let (nearby_lines, nearby_tickets) = section("nearby tickets:")?.into_iter().unzip();
 
-    Input {
+    Ok(Input {
         field_ranges: FieldRanges {
             inner: field_ranges,
         },
         my_ticket,
         nearby_tickets,
+        nearby_lines,
+    })
+}
+
+impl Ranges {
+    /// Sort and merge the ranges, so that no two of them overlap or touch
+    pub fn new(ranges: impl IntoIterator<Item = Range>) -> Self {
+        let mut ranges: Vec<Range> = ranges.into_iter().filter(|r| !r.is_empty()).collect();
+        ranges.sort_unstable_by_key(|r| *r.start());
+
+        let mut intervals: Vec<Range> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match intervals.last_mut() {
+                Some(last) if *range.start() <= last.end().saturating_add(1) => {
+                    *last = *last.start()..=*last.end().max(range.end());
+                }
+                _ => intervals.push(range),
+            }
+        }
+
+        Ranges { intervals }
+    }
+
+    pub fn contains(&self, number: u64) -> bool {
+        // the first interval that does not end before the number
+        let i = self.intervals.partition_point(|r| *r.end() < number);
+        self.intervals
+            .get(i)
+            .is_some_and(|range| range.contains(&number))
+    }
+
+    /// The interval closest to the number, and how far away it is
+    pub fn nearest(&self, number: u64) -> Option<(Range, u64)> {
+        let i = self.intervals.partition_point(|r| *r.end() < number);
+        let distance = |r: &Range| {
+            if number < *r.start() {
+                r.start() - number
+            } else {
+                number.saturating_sub(*r.end())
+            }
+        };
+
+        // only the intervals on either side of the number can be the closest
+        self.intervals[i.saturating_sub(1)..]
+            .iter()
+            .take(2)
+            .map(|r| (r.clone(), distance(r)))
+            .min_by_key(|(_, distance)| *distance)
+    }
+
+    pub fn intervals(&self) -> &[Range] {
+        &self.intervals
     }
 }
 
@@ -73,85 +203,156 @@ impl FieldRanges<'_> {
     pub fn number_valid_for(&self, number: u64) -> impl Iterator<Item = &str> {
         self.inner
             .iter()
-            .filter(move |(_, (r1, r2))| r1.contains(&number) || r2.contains(&number))
+            .filter(move |(_, ranges)| ranges.contains(number))
             .map(|(&name, _)| name)
     }
 }
 
-pub fn part1(input: &str) -> u64 {
-    let input = parse(input);
+impl<'a> Input<'a> {
+    /// Work out the fields from the nearby tickets, and use them to decode your ticket
+    pub fn decode_my_ticket(&self) -> Result<decoder::DecodedTicket<'a>, AssignError<'a>> {
+        let mut decoder = decoder::TicketDecoder::new(&self.field_ranges, self.my_ticket.len());
+        for ticket in &self.nearby_tickets {
+            decoder.add(ticket);
+        }
+        decoder.decode(&self.my_ticket)
+    }
+}
 
-    input
-        .nearby_tickets
-        .iter()
-        .flat_map(|fields| fields.iter())
-        .map(|&field| field)
-        .filter(|&field| input.field_ranges.number_valid_for(field).next().is_none())
-        .sum()
+/// The possible fields of every column, as indices into the sorted field names
+struct Candidates<'a> {
+    names: Vec<&'a str>,
+    candidates: Vec<Vec<usize>>,
 }
 
-pub fn part2(input: &str) -> u64 {
-    let Input {
-        field_ranges,
-        mut nearby_tickets,
-        my_ticket,
-    } = parse(input);
+impl<'a> Candidates<'a> {
+    fn new(possibilities: &[HashSet<&'a str>]) -> Self {
+        let mut names: Vec<&str> = possibilities.iter().flatten().copied().collect();
+        names.sort_unstable();
+        names.dedup();
 
-    // discard tickets with invalid fields
-    nearby_tickets.retain(|ticket| {
-        ticket
+        let index = |name| names.binary_search(name).unwrap();
+        let candidates: Vec<Vec<usize>> = possibilities
             .iter()
-            .map(|&field| field_ranges.number_valid_for(field).next().is_some())
-            .all(|retain| retain)
-    });
-
-    let mut field_possibilities: Vec<HashSet<&str>> = my_ticket
-        .iter()
-        .map(|_| field_ranges.inner.keys().copied().collect())
-        .collect();
-
-    for ticket in nearby_tickets {
-        for (i, &field) in ticket.iter().enumerate() {
-            let valid_for: HashSet<&str> = field_ranges.number_valid_for(field).collect();
-            field_possibilities[i].retain(|field| valid_for.contains(field));
-        }
+            .map(|set| {
+                let mut fields: Vec<usize> = set.iter().map(index).collect();
+                fields.sort_unstable();
+                fields
+            })
+            .collect();
+
+        Candidates { names, candidates }
+    }
+
+    /// A field for every column, or the columns that can't be given one
+    fn matching(&self) -> Result<Vec<usize>, Vec<usize>> {
+        matching::perfect_matching(&self.candidates, self.names.len())
     }
 
-    let mut field_names: Vec<Option<&str>> = vec![None; field_possibilities.len()];
-    loop {
-        // find next field with only one possible match
-        let (i, next_set) = field_possibilities
-            .iter_mut()
+    fn is_unique(&self, field_of: &[usize]) -> bool {
+        matching::is_unique(&self.candidates, self.names.len(), field_of)
+    }
+
+    fn name_all(&self, fields: impl IntoIterator<Item = usize>) -> Vec<&'a str> {
+        fields.into_iter().map(|field| self.names[field]).collect()
+    }
+}
+
+/// Give every column one of its possible fields, so that no two columns have the same field
+///
+/// Fails if there is no such assignment, or if there is more than one.
+pub fn assign_fields<'a>(
+    possibilities: &[HashSet<&'a str>],
+) -> Result<Vec<&'a str>, AssignError<'a>> {
+    let candidates = Candidates::new(possibilities);
+    let field_of = candidates.matching().map_err(AssignError::Unsolvable)?;
+
+    if candidates.is_unique(&field_of) {
+        return Ok(candidates.name_all(field_of));
+    }
+
+    let ambiguous: Vec<(usize, Vec<&str>)> =
+        matching::alternatives(&candidates.candidates, candidates.names.len())
+            .into_iter()
             .enumerate()
-            .filter(|(_, set)| set.len() == 1)
-            .next()
-            .unwrap();
+            .filter(|(_, fields)| fields.len() > 1)
+            .map(|(column, fields)| (column, candidates.name_all(fields)))
+            .collect();
+    Err(AssignError::Ambiguous(ambiguous))
+}
 
-        let next = next_set.drain().next().unwrap();
+/// The assignment of fields to columns, if there is exactly one
+///
+/// Unlike [assign_fields], this doesn't work out why there isn't, which is expensive while the
+/// assignment is still ambiguous.
+pub fn unique_assignment<'a>(possibilities: &[HashSet<&'a str>]) -> Option<Vec<&'a str>> {
+    let candidates = Candidates::new(possibilities);
+    let field_of = candidates.matching().ok()?;
+    candidates
+        .is_unique(&field_of)
+        .then(|| candidates.name_all(field_of))
+}
 
-        for set in field_possibilities.iter_mut() {
-            set.remove(next);
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ParseErrorKind::MalformedRule(rule) => {
+                write!(
+                    f,
+                    "expected a rule like \"name: 1-3 or 5-7\", got \"{}\"",
+                    rule
+                )
+            }
+            ParseErrorKind::InvalidRange(range) => write!(f, "invalid range: \"{}\"", range),
+            ParseErrorKind::DuplicateField(name) => write!(f, "duplicate field: \"{}\"", name),
+            ParseErrorKind::InvalidNumber(num) => write!(f, "invalid number: \"{}\"", num),
+            ParseErrorKind::MissingSection(header) => write!(f, "expected \"{}\"", header),
         }
+    }
+}
 
-        field_names[i] = Some(next);
-
-        if field_names.iter().all(|name| name.is_some()) {
-            break;
+impl fmt::Display for AssignError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssignError::Unsolvable(columns) => {
+                write!(f, "no valid field assignment for columns {:?}", columns)
+            }
+            AssignError::Ambiguous(columns) => {
+                write!(f, "field assignment is ambiguous:")?;
+                for (column, fields) in columns {
+                    write!(
+                        f,
+                        "\n  column {} could be any of {}",
+                        column,
+                        fields.join(", ")
+                    )?;
+                }
+                Ok(())
+            }
         }
     }
+}
+
+pub fn part1(input: &str) -> u64 {
+    let input = parse(input);
+    report::report(&input).error_rate()
+}
 
-    field_names
-        .iter()
-        .flatten()
-        .enumerate()
-        .filter(|(_, name)| name.starts_with("departure"))
-        .map(|(i, _name)| my_ticket[i])
-        .product()
+pub fn part2(input: &str) -> u64 {
+    parse(input)
+        .decode_my_ticket()
+        .unwrap_or_else(|err| panic!("{}", err))
+        .product(|name| name.starts_with("departure"))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{
+        assign_fields, parse, parse_strict, part1, part2, unique_assignment, AssignError,
+        ParseError, ParseErrorKind, Ranges,
+    };
+    use std::collections::HashSet;
 
     #[test]
     pub fn test_part1() {
@@ -164,4 +365,159 @@ mod tests {
         let input = include_str!("test-input");
         assert_eq!(part2(input), 1337);
     }
+
+    #[test]
+    pub fn test_assign_fields() {
+        let set = |fields: &[&'static str]| fields.iter().copied().collect::<HashSet<_>>();
+
+        let possibilities = [
+            set(&["class", "row", "seat"]),
+            set(&["row"]),
+            set(&["row", "seat"]),
+        ];
+        assert_eq!(
+            assign_fields(&possibilities),
+            Ok(vec!["class", "row", "seat"])
+        );
+
+        let possibilities = [
+            set(&["class", "row"]),
+            set(&["class", "row"]),
+            set(&["seat"]),
+        ];
+        assert_eq!(
+            assign_fields(&possibilities),
+            Err(AssignError::Ambiguous(vec![
+                (0, vec!["class", "row"]),
+                (1, vec!["class", "row"]),
+            ]))
+        );
+
+        let possibilities = [set(&["row"]), set(&["row"]), set(&["seat"])];
+        assert_eq!(
+            assign_fields(&possibilities),
+            Err(AssignError::Unsolvable(vec![1]))
+        );
+    }
+
+    #[test]
+    pub fn test_unique_assignment() {
+        let set = |fields: &[&'static str]| fields.iter().copied().collect::<HashSet<_>>();
+
+        let unique = [
+            set(&["class", "row", "seat"]),
+            set(&["row"]),
+            set(&["row", "seat"]),
+        ];
+        assert_eq!(
+            unique_assignment(&unique),
+            Some(vec!["class", "row", "seat"])
+        );
+
+        let ambiguous = [
+            set(&["class", "row"]),
+            set(&["class", "row"]),
+            set(&["seat"]),
+        ];
+        assert_eq!(unique_assignment(&ambiguous), None);
+
+        let unsolvable = [set(&["row"]), set(&["row"]), set(&["seat"])];
+        assert_eq!(unique_assignment(&unsolvable), None);
+    }
+
+    #[test]
+    pub fn test_assign_example() {
+        let input = parse(include_str!("test-input2"));
+        let possibilities: Vec<HashSet<&str>> = (0..input.my_ticket.len())
+            .map(|column| {
+                let all = input.field_ranges.inner.keys().copied().collect();
+                input
+                    .nearby_tickets
+                    .iter()
+                    .fold(all, |set: HashSet<&str>, ticket| {
+                        let valid: HashSet<&str> = input
+                            .field_ranges
+                            .number_valid_for(ticket[column])
+                            .collect();
+                        &set & &valid
+                    })
+            })
+            .collect();
+
+        assert_eq!(
+            assign_fields(&possibilities),
+            Ok(vec!["row", "class", "seat"])
+        );
+    }
+
+    #[test]
+    pub fn test_ranges() {
+        let ranges = Ranges::new(vec![8..=9, 1..=3, 2..=5, 6..=6, 11..=20, 15..=16]);
+        assert_eq!(ranges.intervals(), &[1..=6, 8..=9, 11..=20]);
+
+        let contained: Vec<u64> = (0..22).filter(|&n| ranges.contains(n)).collect();
+        let expected: Vec<u64> = (1..=6).chain(8..=9).chain(11..=20).collect();
+        assert_eq!(contained, expected);
+
+        assert!(!Ranges::new(vec![]).contains(0));
+        assert!(Ranges::new(vec![u64::MAX - 1..=u64::MAX, 0..=0]).contains(u64::MAX));
+    }
+
+    #[test]
+    pub fn test_parse_rules() {
+        let input = "one: 1-3\n\
+                     three: 5-7 or 10-12 or 20-21\n\
+                     \n\
+                     your ticket:\n\
+                     1,5\n\
+                     \n\
+                     nearby tickets:\n\
+                     11,2\n\
+                     20,4\n";
+        let input = parse_strict(input).unwrap();
+        assert_eq!(input.field_ranges.inner["one"].intervals(), &[1..=3]);
+        assert_eq!(
+            input.field_ranges.inner["three"].intervals(),
+            &[5..=7, 10..=12, 20..=21]
+        );
+        assert_eq!(input.my_ticket, vec![1, 5]);
+        assert_eq!(input.nearby_tickets, vec![vec![11, 2], vec![20, 4]]);
+    }
+
+    #[test]
+    pub fn test_parse_errors() {
+        let error = |input: &str| parse_strict(input).unwrap_err();
+        let tickets = "\nyour ticket:\n1\n\nnearby tickets:\n1\n";
+
+        assert_eq!(
+            error(&format!("a: 1-2\nb 3-4\n{}", tickets)),
+            ParseError {
+                line: 2,
+                kind: ParseErrorKind::MalformedRule("b 3-4".to_string()),
+            }
+        );
+        assert_eq!(
+            error(&format!("a: 1-2 or\n{}", tickets)).kind,
+            ParseErrorKind::InvalidRange("1-2 or".to_string())
+        );
+        assert_eq!(
+            error(&format!("a: 4-2\n{}", tickets)).kind,
+            ParseErrorKind::InvalidRange("4-2".to_string())
+        );
+        assert_eq!(
+            error(&format!("a: 1-2\na: 3-4\n{}", tickets)).kind,
+            ParseErrorKind::DuplicateField("a".to_string())
+        );
+        assert_eq!(
+            error("a: 1-2\n\nyour ticket:\n1,x\n"),
+            ParseError {
+                line: 4,
+                kind: ParseErrorKind::InvalidNumber("x".to_string()),
+            }
+        );
+        assert_eq!(
+            error("a: 1-2\n\nyour ticket:\n1\n").to_string(),
+            "line 5: expected \"nearby tickets:\""
+        );
+    }
 }
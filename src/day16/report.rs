@@ -0,0 +1,211 @@
+//! Reports of the nearby tickets that contain values which are not valid for any field
+
+use super::{Input, Range};
+use std::fmt::{self, Write};
+
+/// The closest a value came to being valid for a field
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Miss<'a> {
+    pub field: &'a str,
+    pub range: Range,
+    pub distance: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidValue<'a> {
+    pub column: usize,
+    pub value: u64,
+
+    /// The nearest range of any field, or None if there are no fields
+    pub nearest: Option<Miss<'a>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidTicket<'a> {
+    /// The input line of the ticket, starting at 1
+    pub line: usize,
+    pub values: Vec<InvalidValue<'a>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report<'a> {
+    pub tickets: Vec<InvalidTicket<'a>>,
+}
+
+/// Find every nearby ticket with values that are not valid for any field
+pub fn report<'a>(input: &Input<'a>) -> Report<'a> {
+    let ranges = &input.field_ranges.inner;
+
+    let nearest = |value| {
+        ranges
+            .iter()
+            .filter_map(|(&field, ranges)| {
+                let (range, distance) = ranges.nearest(value)?;
+                Some(Miss {
+                    field,
+                    range,
+                    distance,
+                })
+            })
+            // break ties by name, since the fields are stored in a HashMap
+            .min_by_key(|miss| (miss.distance, miss.field, *miss.range.start()))
+    };
+
+    let tickets = input
+        .nearby_tickets
+        .iter()
+        .zip(&input.nearby_lines)
+        .map(|(ticket, &line)| InvalidTicket {
+            line,
+            values: ticket
+                .iter()
+                .enumerate()
+                .filter(|&(_, &value)| !ranges.values().any(|r| r.contains(value)))
+                .map(|(column, &value)| InvalidValue {
+                    column,
+                    value,
+                    nearest: nearest(value),
+                })
+                .collect(),
+        })
+        .filter(|ticket| !ticket.values.is_empty())
+        .collect();
+
+    Report { tickets }
+}
+
+/// Write a string as a JSON string literal
+fn json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+impl Report<'_> {
+    /// The sum of every invalid value, i.e. the ticket scanning error rate
+    pub fn error_rate(&self) -> u64 {
+        self.tickets
+            .iter()
+            .flat_map(|ticket| &ticket.values)
+            .map(|value| value.value)
+            .sum()
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\"invalid_tickets\":[");
+        for (i, ticket) in self.tickets.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write!(out, "{{\"line\":{},\"values\":[", ticket.line).unwrap();
+            for (j, value) in ticket.values.iter().enumerate() {
+                if j > 0 {
+                    out.push(',');
+                }
+                write!(
+                    out,
+                    "{{\"column\":{},\"value\":{},\"nearest\":",
+                    value.column, value.value
+                )
+                .unwrap();
+                match &value.nearest {
+                    None => out.push_str("null"),
+                    Some(miss) => {
+                        out.push_str("{\"field\":");
+                        json_string(&mut out, miss.field);
+                        write!(
+                            out,
+                            ",\"from\":{},\"to\":{},\"distance\":{}}}",
+                            miss.range.start(),
+                            miss.range.end(),
+                            miss.distance
+                        )
+                        .unwrap();
+                    }
+                }
+                out.push('}');
+            }
+            out.push_str("]}");
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.tickets.is_empty() {
+            return writeln!(f, "all nearby tickets are valid");
+        }
+
+        for ticket in &self.tickets {
+            writeln!(f, "line {}:", ticket.line)?;
+            for value in &ticket.values {
+                write!(f, "  column {}: {} ", value.column, value.value)?;
+                match &value.nearest {
+                    Some(miss) => writeln!(
+                        f,
+                        "missed {} {}-{} by {}",
+                        miss.field,
+                        miss.range.start(),
+                        miss.range.end(),
+                        miss.distance
+                    )?,
+                    None => writeln!(f, "is not near any field")?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse;
+    use super::report;
+
+    #[test]
+    pub fn test_report() {
+        let input = parse(include_str!("test-input"));
+        let report = report(&input);
+        assert_eq!(report.error_rate(), 71);
+
+        assert_eq!(
+            report.to_string(),
+            "line 10:\n  column 1: 4 missed class 1-3 by 1\n\
+             line 11:\n  column 0: 55 missed seat 45-50 by 5\n\
+             line 12:\n  column 2: 12 missed row 6-11 by 1\n"
+        );
+
+        assert_eq!(
+            report.to_json(),
+            concat!(
+                r#"{"invalid_tickets":["#,
+                r#"{"line":10,"values":[{"column":1,"value":4,"nearest":"#,
+                r#"{"field":"class","from":1,"to":3,"distance":1}}]},"#,
+                r#"{"line":11,"values":[{"column":0,"value":55,"nearest":"#,
+                r#"{"field":"seat","from":45,"to":50,"distance":5}}]},"#,
+                r#"{"line":12,"values":[{"column":2,"value":12,"nearest":"#,
+                r#"{"field":"row","from":6,"to":11,"distance":1}}]}"#,
+                r#"]}"#,
+            )
+        );
+    }
+
+    #[test]
+    pub fn test_report_valid() {
+        let input = parse(include_str!("test-input2"));
+        let report = report(&input);
+        assert!(report.tickets.is_empty());
+        assert_eq!(report.to_string(), "all nearby tickets are valid\n");
+        assert_eq!(report.to_json(), r#"{"invalid_tickets":[]}"#);
+    }
+}
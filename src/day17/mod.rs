@@ -17,37 +17,117 @@ pub fn parse<'a>(input: &'a str) -> impl Iterator<Item = XY> + 'a {
         .map(|(coord, _)| coord)
 }
 
+/// Run one cycle of the pocket dimension in three dimensions
+pub fn cycle3(active: &HashSet<XYZ>) -> HashSet<XYZ> {
+    // create an iterator over inactive spaces that will *become* active
+    let new_active = active
+        .iter()
+        .copied()
+        // all neighbors of active cubes
+        .flat_map(neighbors3)
+        // ...that are non-active
+        .filter(|neighbor| !active.contains(neighbor))
+        // ...with exactly 3 active neighbors
+        .filter(|&inactive| {
+            neighbors3(inactive)
+                .filter(|neighbor| active.contains(neighbor))
+                .count()
+                == 3
+        });
+
+    // create an iterator over active spaces that will *remain* active
+    let old_active = active.iter().copied().filter(|&coord| {
+        [2, 3].contains(
+            &neighbors3(coord)
+                .filter(|neighbor| active.contains(neighbor))
+                .count(),
+        )
+    });
+
+    // create the new set of active cubes
+    new_active.chain(old_active).collect()
+}
+
+/// Run one cycle of the pocket dimension in four dimensions
+pub fn cycle4(active: &HashSet<XYZW>) -> HashSet<XYZW> {
+    // create an iterator over inactive spaces that will *become* active
+    let new_active = active
+        .iter()
+        .copied()
+        // all neighbors of active cubes
+        .flat_map(neighbors4)
+        // ...that are non-active
+        .filter(|neighbor| !active.contains(neighbor))
+        // ...with exactly 3 active neighbors
+        .filter(|&inactive| {
+            neighbors4(inactive)
+                .filter(|neighbor| active.contains(neighbor))
+                .count()
+                == 3
+        });
+
+    // create an iterator over active spaces that will *remain* active
+    let old_active = active.iter().copied().filter(|&coord| {
+        [2, 3].contains(
+            &neighbors4(coord)
+                .filter(|neighbor| active.contains(neighbor))
+                .count(),
+        )
+    });
+
+    // create the new set of active cubes
+    new_active.chain(old_active).collect()
+}
+
+/// Render the active cubes as a series of 2D slices, one for every z (and w) coordinate
+pub fn render_slices<const N: usize>(active: &HashSet<[i32; N]>) -> String {
+    const AXES: [&str; 2] = ["z", "w"];
+
+    if active.is_empty() {
+        return String::new();
+    }
+
+    let min_x = active.iter().map(|c| c[0]).min().unwrap();
+    let max_x = active.iter().map(|c| c[0]).max().unwrap();
+    let min_y = active.iter().map(|c| c[1]).min().unwrap();
+    let max_y = active.iter().map(|c| c[1]).max().unwrap();
+
+    // every combination of higher dimensional coordinates that has an active cube
+    let mut slices: Vec<&[i32]> = active.iter().map(|c| &c[2..]).collect();
+    slices.sort_unstable();
+    slices.dedup();
+
+    let mut out = String::new();
+    for slice in slices {
+        let label: Vec<String> = AXES
+            .iter()
+            .zip(slice.iter())
+            .map(|(axis, v)| format!("{}={}", axis, v))
+            .collect();
+        out.push_str(&label.join(", "));
+        out.push('\n');
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let mut coord = [0; N];
+                coord[0] = x;
+                coord[1] = y;
+                coord[2..].copy_from_slice(slice);
+                out.push(if active.contains(&coord) { '#' } else { '.' });
+            }
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
 pub fn part1(input: &str) -> usize {
     let mut active: HashSet<XYZ> = parse(input).map(|[x, y]| [x, y, 0]).collect();
 
     for _cycle in 0..6 {
-        // create an iterator over inactive spaces that will *become* active
-        let new_active = active
-            .iter()
-            .copied()
-            // all neighbors of active cubes
-            .flat_map(|coord| neighbors3(coord))
-            // ...that are non-active
-            .filter(|neighbor| !active.contains(neighbor))
-            // ...with exactly 3 active neighbors
-            .filter(|&inactive| {
-                neighbors3(inactive)
-                    .filter(|neighbor| active.contains(neighbor))
-                    .count()
-                    == 3
-            });
-
-        // create an iterator over active spaces that will *remain* active
-        let old_active = active.iter().copied().filter(|&coord| {
-            [2, 3].contains(
-                &neighbors3(coord)
-                    .filter(|neighbor| active.contains(neighbor))
-                    .count(),
-            )
-        });
-
-        // create the new set of active cubes
-        active = new_active.chain(old_active).collect();
+        active = cycle3(&active);
     }
 
     active.len()
@@ -57,33 +137,7 @@ pub fn part2(input: &str) -> usize {
     let mut active: HashSet<XYZW> = parse(input).map(|[x, y]| [x, y, 0, 0]).collect();
 
     for _cycle in 0..6 {
-        // create an iterator over inactive spaces that will *become* active
-        let new_active = active
-            .iter()
-            .copied()
-            // all neighbors of active cubes
-            .flat_map(|coord| neighbors4(coord))
-            // ...that are non-active
-            .filter(|neighbor| !active.contains(neighbor))
-            // ...with exactly 3 active neighbors
-            .filter(|&inactive| {
-                neighbors4(inactive)
-                    .filter(|neighbor| active.contains(neighbor))
-                    .count()
-                    == 3
-            });
-
-        // create an iterator over active spaces that will *remain* active
-        let old_active = active.iter().copied().filter(|&coord| {
-            [2, 3].contains(
-                &neighbors4(coord)
-                    .filter(|neighbor| active.contains(neighbor))
-                    .count(),
-            )
-        });
-
-        // create the new set of active cubes
-        active = new_active.chain(old_active).collect();
+        active = cycle4(&active);
     }
 
     active.len()
@@ -108,7 +162,8 @@ fn neighbors4([x, y, z, w]: XYZW) -> impl Iterator<Item = XYZW> {
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{cycle3, parse, part1, part2, render_slices, XYZ};
+    use std::collections::HashSet;
 
     #[test]
     pub fn test_part1() {
@@ -121,4 +176,29 @@ mod tests {
         let input = include_str!("test-input");
         assert_eq!(part2(input), 848);
     }
+
+    #[test]
+    pub fn test_render_slices() {
+        let input = include_str!("test-input");
+        let active: HashSet<XYZ> = parse(input).map(|[x, y]| [x, y, 0]).collect();
+        let active = cycle3(&active);
+        let expected = "\
+z=-1
+#..
+..#
+.#.
+
+z=0
+#.#
+.##
+.#.
+
+z=1
+#..
+..#
+.#.
+
+";
+        assert_eq!(render_slices(&active), expected);
+    }
 }
@@ -1,7 +1,10 @@
-#[derive(Clone, Copy, Debug)]
+use std::cmp::Ordering;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Token {
     Op(Op),
-    Num(u64),
+    Num(i64),
     LParen,
     RParen,
 }
@@ -9,165 +12,646 @@ pub enum Token {
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Op {
     Add,
+    Sub,
     Mul,
+    Div,
+    Rem,
+    Pow,
+}
+
+/// Which way a chain of operators with the same precedence groups
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Assoc {
+    /// `a - b - c` is `(a - b) - c`
+    Left,
+
+    /// `a ^ b ^ c` is `a ^ (b ^ c)`
+    Right,
+}
+
+/// The precedence level and associativity of every operator
+///
+/// Operators on a higher level bind more tightly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrecedenceTable {
+    levels: [(u32, Assoc); Op::ALL.len()],
+
+    /// The level of the `-` in front of a negative number
+    negation: u32,
 }
 
 /// A number, of an operator. Used for Reverse Polish Notation.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum RPN {
+pub enum RPN {
     Op(Op),
-    Num(u64),
-}
-
-pub fn parse<'a>(input: &'a str) -> impl Iterator<Item = impl Iterator<Item = Token> + 'a> + 'a {
-    input.lines().map(|line| {
-        line.chars().filter_map(|c| {
-            Some(match c {
-                ')' => Token::RParen,
-                '(' => Token::LParen,
-                '*' => Token::Op(Op::Mul),
-                '+' => Token::Op(Op::Add),
-                '0' => Token::Num(0),
-                '1' => Token::Num(1),
-                '2' => Token::Num(2),
-                '3' => Token::Num(3),
-                '4' => Token::Num(4),
-                '5' => Token::Num(5),
-                '6' => Token::Num(6),
-                '7' => Token::Num(7),
-                '8' => Token::Num(8),
-                '9' => Token::Num(9),
-                ' ' => return None,
-                _ => panic!("unexpected char: '{}'", c),
-            })
-        })
-    })
+    Num(i64),
+
+    /// Negate the number on top of the stack
+    Neg,
+}
+
+/// An expression tree
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Num(i64),
+    Op(Op, Box<Expr>, Box<Expr>),
+    Neg(Box<Expr>),
+}
+
+/// The columns of a token, counted in chars from 0
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+
+    /// One past the last column
+    pub end: usize,
+}
+
+/// The value of a line of input
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineResult<'a> {
+    /// The line number, starting at 1
+    pub line: usize,
+    pub source: &'a str,
+    pub expr: Expr,
+    pub value: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    /// The line of the error, starting at 1
+    pub line: usize,
+
+    /// Where on the line the error is, if it's a syntax error
+    pub span: Option<Span>,
+
+    /// The text of the line
+    pub source: String,
+
+    pub kind: ErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    UnexpectedChar(char),
+
+    /// A literal that does not fit in an i64
+    NumberTooLarge(String),
+
+    /// A `(` without a matching `)`
+    UnclosedParen,
+
+    /// A `)` without a matching `(`
+    UnmatchedParen,
+
+    /// An operator, or a `)`, where a number or a `(` was expected
+    MissingOperand,
+
+    /// A number or a `(` where an operator was expected
+    MissingOperator,
+
+    /// An operator at the end of the line, or right before a `)`
+    TrailingOperator(Op),
+
+    /// The result of an operation does not fit in an i64
+    Overflow(Op, i64, i64),
+
+    DivisionByZero,
+    NegativeExponent(i64),
+}
+
+/// Split a line into tokens
+///
+/// Numbers may have any number of digits. A `-` directly in front of a number, where an operand
+/// is expected, makes it negative. Anywhere else it's a subtraction. How tightly that sign binds
+/// is up to the [PrecedenceTable] the tokens are parsed with.
+pub fn tokenize(line: &str) -> Result<Vec<(Span, Token)>, (Span, ErrorKind)> {
+    let mut tokens: Vec<(Span, Token)> = vec![];
+    let mut chars = line.char_indices().enumerate().peekable();
+
+    while let Some((column, (start, c))) = chars.next() {
+        // an operand is expected at the start, and after an operator or opening parenthesis
+        let operand_expected = matches!(
+            tokens.last(),
+            None | Some((_, Token::Op(_))) | Some((_, Token::LParen))
+        );
+        let next_is_digit = chars.peek().is_some_and(|(_, (_, c))| c.is_ascii_digit());
+
+        let mut span = Span {
+            start: column,
+            end: column + 1,
+        };
+
+        let token = match c {
+            ' ' => continue,
+            ')' => Token::RParen,
+            '(' => Token::LParen,
+            '*' => Token::Op(Op::Mul),
+            '+' => Token::Op(Op::Add),
+            '/' => Token::Op(Op::Div),
+            '%' => Token::Op(Op::Rem),
+            '^' => Token::Op(Op::Pow),
+            '-' if !(operand_expected && next_is_digit) => Token::Op(Op::Sub),
+            '-' | '0'..='9' => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(column, (i, c))) =
+                    chars.peek().filter(|(_, (_, c))| c.is_ascii_digit())
+                {
+                    end = i + c.len_utf8();
+                    span.end = column + 1;
+                    chars.next();
+                }
+
+                let literal = &line[start..end];
+                let num = literal
+                    .parse()
+                    .map_err(|_| (span, ErrorKind::NumberTooLarge(literal.to_string())))?;
+                Token::Num(num)
+            }
+            _ => return Err((span, ErrorKind::UnexpectedChar(c))),
+        };
+
+        tokens.push((span, token));
+    }
+
+    Ok(tokens)
+}
+
+/// Check that the tokens form a valid expression
+pub fn validate(tokens: &[(Span, Token)]) -> Result<(), (Span, ErrorKind)> {
+    // the opening parentheses that have not been closed yet
+    let mut open = vec![];
+    let mut operand_expected = true;
+    let mut last: Option<(Span, Token)> = None;
+
+    for &(span, token) in tokens {
+        match token {
+            Token::Num(_) | Token::LParen if !operand_expected => {
+                return Err((span, ErrorKind::MissingOperator));
+            }
+            Token::Num(_) => operand_expected = false,
+            Token::LParen => open.push(span),
+            Token::Op(_) if operand_expected => return Err((span, ErrorKind::MissingOperand)),
+            Token::Op(_) => operand_expected = true,
+            Token::RParen if open.is_empty() => return Err((span, ErrorKind::UnmatchedParen)),
+            Token::RParen if operand_expected => {
+                return Err(match last {
+                    Some((span, Token::Op(op))) => (span, ErrorKind::TrailingOperator(op)),
+                    _ => (span, ErrorKind::MissingOperand),
+                });
+            }
+            Token::RParen => {
+                open.pop();
+            }
+        }
+
+        last = Some((span, token));
+    }
+
+    if let Some((span, Token::Op(op))) = last {
+        return Err((span, ErrorKind::TrailingOperator(op)));
+    }
+
+    if let Some(&span) = open.first() {
+        return Err((span, ErrorKind::UnclosedParen));
+    }
+
+    if operand_expected {
+        let end = Span { start: 0, end: 0 };
+        return Err((
+            last.map_or(end, |(span, _)| span),
+            ErrorKind::MissingOperand,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Parse a line into reverse polish notation, ordered according to the precedence table
+pub fn parse_rpn(line: &str, table: &PrecedenceTable) -> Result<Vec<RPN>, (Span, ErrorKind)> {
+    let tokens = tokenize(line)?;
+    validate(&tokens)?;
+
+    to_rpn(&tokens, table)
+}
+
+/// Parse a line into an expression tree, grouped according to the precedence table
+pub fn parse_expr(line: &str, table: &PrecedenceTable) -> Result<Expr, (Span, ErrorKind)> {
+    Ok(Expr::from_rpn(&parse_rpn(line, table)?))
 }
 
-/// Returns the input equation as reverse polish notation
+/// Every non-empty line, along with its line number
+fn lines(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty())
+}
+
+/// Returns the tokens of an equation as reverse polish notation
 ///
-/// The `has_higher_prescedence` function is used to determine if one operator binds more tightly
-/// than another.
-fn to_rpn<F>(input: &str, has_higher_prescedence: F) -> Vec<RPN>
-where
-    F: Fn(Op, Op) -> bool,
-{
+/// The precedence table is used to determine if one operator binds more tightly than another.
+/// The tokens must be validated.
+fn to_rpn(
+    tokens: &[(Span, Token)],
+    table: &PrecedenceTable,
+) -> Result<Vec<RPN>, (Span, ErrorKind)> {
     #[derive(Clone, Copy, Debug)]
     enum StackElem {
         StackFrame,
         Op(Op),
-    }
-
-    /// An infinitive iterator where the first element is true, and the rest are false
-    fn first_true() -> impl Iterator<Item = bool> {
-        use std::iter;
-        iter::once(true).chain(iter::repeat(false))
+        Neg,
     }
 
     let mut stack = vec![];
     let mut output = vec![];
 
-    for (line, is_first) in parse(input).zip(first_true()) {
-        for token in line {
-            match token {
-                Token::Num(num) => output.push(RPN::Num(num)),
-                Token::Op(op) => {
-                    // make sure lhs is computed
-                    match stack.last().copied() {
-                        // stack is empty
-                        None | Some(StackElem::StackFrame) => {}
-
-                        // this operator had a higher prescedence than the previous one
-                        // so we let the previous one wait
-                        Some(StackElem::Op(prev_op)) if has_higher_prescedence(op, prev_op) => {}
-
-                        // in any other case, we pop the previous operation from the stack
-                        Some(StackElem::Op(prev_op)) => {
-                            output.push(RPN::Op(prev_op));
-                            stack.pop();
+    for (i, &(span, token)) in tokens.iter().enumerate() {
+        match token {
+            Token::Num(num) => match tokens.get(i + 1) {
+                // the operator after a negative number binds more tightly than its sign, so the
+                // sign has to wait for that operation
+                Some(&(_, Token::Op(op))) if num < 0 && table.binds_tighter_than_negation(op) => {
+                    let abs = num.checked_neg().ok_or_else(|| {
+                        let literal = (num as i128).abs().to_string();
+                        (span, ErrorKind::NumberTooLarge(literal))
+                    })?;
+                    output.push(RPN::Num(abs));
+                    stack.push(StackElem::Neg);
+                }
+                _ => output.push(RPN::Num(num)),
+            },
+            Token::Op(op) => {
+                // make sure lhs is computed
+                loop {
+                    // if this operator binds more tightly than the previous one,
+                    // we let the previous one wait
+                    let prev = match stack.last() {
+                        Some(&StackElem::Op(prev_op)) if !table.binds_tighter(op, prev_op) => {
+                            RPN::Op(prev_op)
                         }
-                    }
+                        Some(StackElem::Neg) if !table.binds_tighter_than_negation(op) => RPN::Neg,
+                        _ => break,
+                    };
 
-                    // then we push this operator to the stack and wait for rhs to be computed
-                    stack.push(StackElem::Op(op));
+                    // in any other case, we pop the previous operation from the stack
+                    output.push(prev);
+                    stack.pop();
                 }
-                Token::LParen => stack.push(StackElem::StackFrame),
-                Token::RParen => loop {
-                    // pop everything up until the last stackframe
-                    match stack.pop() {
-                        None | Some(StackElem::StackFrame) => break,
-                        Some(StackElem::Op(op)) => output.push(RPN::Op(op)),
-                    }
-                },
+
+                // then we push this operator to the stack and wait for rhs to be computed
+                stack.push(StackElem::Op(op));
             }
+            Token::LParen => stack.push(StackElem::StackFrame),
+            Token::RParen => loop {
+                // pop everything up until the last stackframe
+                match stack.pop() {
+                    None | Some(StackElem::StackFrame) => break,
+                    Some(StackElem::Op(op)) => output.push(RPN::Op(op)),
+                    Some(StackElem::Neg) => output.push(RPN::Neg),
+                }
+            },
         }
+    }
 
-        // pop everything from the stack
-        while let Some(elem) = stack.pop() {
-            match elem {
-                StackElem::Op(op) => output.push(RPN::Op(op)),
-                StackElem::StackFrame => {}
-            }
+    // pop everything from the stack
+    while let Some(elem) = stack.pop() {
+        match elem {
+            StackElem::Op(op) => output.push(RPN::Op(op)),
+            StackElem::Neg => output.push(RPN::Neg),
+            StackElem::StackFrame => {}
         }
+    }
+
+    Ok(output)
+}
+
+impl Op {
+    pub const ALL: [Op; 6] = [Op::Add, Op::Sub, Op::Mul, Op::Div, Op::Rem, Op::Pow];
+
+    pub fn apply(self, lhs: i64, rhs: i64) -> Result<i64, ErrorKind> {
+        let result = match self {
+            Op::Add => lhs.checked_add(rhs),
+            Op::Sub => lhs.checked_sub(rhs),
+            Op::Mul => lhs.checked_mul(rhs),
+            Op::Div | Op::Rem if rhs == 0 => return Err(ErrorKind::DivisionByZero),
+            Op::Div => lhs.checked_div(rhs),
+            Op::Rem => lhs.checked_rem(rhs),
+            Op::Pow if rhs < 0 => return Err(ErrorKind::NegativeExponent(rhs)),
+            Op::Pow => u32::try_from(rhs).ok().and_then(|rhs| lhs.checked_pow(rhs)),
+        };
 
-        if !is_first {
-            // in order to sum all the lines, we inject an addition here
-            output.push(RPN::Op(Op::Add));
+        result.ok_or(ErrorKind::Overflow(self, lhs, rhs))
+    }
+}
+
+impl PrecedenceTable {
+    /// Every operator on the same level, grouping left to right
+    ///
+    /// The sign of a negative number binds more tightly than any operator.
+    pub fn uniform() -> Self {
+        PrecedenceTable {
+            levels: [(0, Assoc::Left); Op::ALL.len()],
+            negation: u32::MAX,
         }
     }
 
-    output
+    /// Part 1: evaluate strictly left to right
+    pub fn part1() -> Self {
+        Self::uniform()
+    }
+
+    /// Part 2: addition and subtraction bind more tightly than the other operators
+    pub fn part2() -> Self {
+        Self::uniform()
+            .with(Op::Add, 1, Assoc::Left)
+            .with(Op::Sub, 1, Assoc::Left)
+    }
+
+    /// Conventional precedence: `^` over negation, which is over `*`, `/` and `%`, which are over
+    /// `+` and `-`. So `-2 ^ 2` is -4.
+    pub fn standard() -> Self {
+        Self::uniform()
+            .with(Op::Mul, 1, Assoc::Left)
+            .with(Op::Div, 1, Assoc::Left)
+            .with(Op::Rem, 1, Assoc::Left)
+            .with_negation(2)
+            .with(Op::Pow, 3, Assoc::Right)
+    }
+
+    /// Set the level and associativity of an operator
+    pub fn with(mut self, op: Op, level: u32, assoc: Assoc) -> Self {
+        self.levels[op as usize] = (level, assoc);
+        self
+    }
+
+    /// Set the level of the `-` in front of a negative number
+    pub fn with_negation(mut self, level: u32) -> Self {
+        self.negation = level;
+        self
+    }
+
+    pub fn negation(&self) -> u32 {
+        self.negation
+    }
+
+    /// Whether `op`, right after a negative number, binds more tightly than the number's sign
+    pub fn binds_tighter_than_negation(&self, op: Op) -> bool {
+        self.level(op) > self.negation
+    }
+
+    pub fn level(&self, op: Op) -> u32 {
+        self.levels[op as usize].0
+    }
+
+    pub fn assoc(&self, op: Op) -> Assoc {
+        self.levels[op as usize].1
+    }
+
+    /// Whether `op` binds more tightly than `prev`, an operator to the left of it
+    pub fn binds_tighter(&self, op: Op, prev: Op) -> bool {
+        match self.level(op).cmp(&self.level(prev)) {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+
+            // on the same level, the associativity of the new operator decides
+            Ordering::Equal => self.assoc(op) == Assoc::Right,
+        }
+    }
 }
 
-fn evalutate_rpn(ops: &[RPN]) -> u64 {
-    let mut stack = Vec::new();
+impl Expr {
+    /// Build an expression tree from reverse polish notation
+    ///
+    /// The notation must come from validated tokens.
+    fn from_rpn(ops: &[RPN]) -> Expr {
+        let mut stack = Vec::new();
+
+        for &op in ops {
+            match op {
+                RPN::Num(num) => stack.push(Expr::Num(num)),
+                RPN::Op(op) => {
+                    let rhs = stack.pop().expect("invalid op arg count, was 0");
+                    let lhs = stack.pop().expect("invalid op arg count, was 1");
+                    stack.push(Expr::Op(op, Box::new(lhs), Box::new(rhs)));
+                }
+                RPN::Neg => {
+                    let expr = stack.pop().expect("invalid neg arg count, was 0");
+                    stack.push(Expr::Neg(Box::new(expr)));
+                }
+            }
+        }
+
+        debug_assert_eq!(
+            stack.len(),
+            1,
+            "invalid input, stack len was {} instead of 1",
+            stack.len()
+        );
+
+        stack.pop().unwrap()
+    }
+
+    pub fn eval(&self) -> Result<i64, ErrorKind> {
+        match self {
+            &Expr::Num(num) => Ok(num),
+            Expr::Op(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.eval()?, rhs.eval()?);
+                op.apply(lhs, rhs)
+            }
+            Expr::Neg(expr) => {
+                let value = expr.eval()?;
+                value
+                    .checked_neg()
+                    .ok_or(ErrorKind::Overflow(Op::Sub, 0, value))
+            }
+        }
+    }
+
+    /// Draw the expression as a tree, with one node per line
+    pub fn tree(&self) -> String {
+        fn draw(expr: &Expr, prefix: &str, out: &mut String) {
+            match expr {
+                Expr::Num(num) => out.push_str(&format!("{}\n", num)),
+                Expr::Op(op, lhs, rhs) => {
+                    out.push_str(&format!("{}\n", op));
 
-    for &op in ops {
-        match op {
-            RPN::Num(num) => stack.push(num),
-            RPN::Op(op) => {
-                let rhs = stack.pop().expect("invalid op arg count, was 0");
-                let lhs = stack.pop().expect("invalid op arg count, was 1");
+                    out.push_str(&format!("{}├── ", prefix));
+                    draw(lhs, &format!("{}│   ", prefix), out);
 
-                let result = match op {
-                    Op::Add => lhs + rhs,
-                    Op::Mul => lhs * rhs,
-                };
+                    out.push_str(&format!("{}└── ", prefix));
+                    draw(rhs, &format!("{}    ", prefix), out);
+                }
+                Expr::Neg(expr) => {
+                    out.push_str("neg\n");
 
-                stack.push(result);
+                    out.push_str(&format!("{}└── ", prefix));
+                    draw(expr, &format!("{}    ", prefix), out);
+                }
             }
         }
+
+        let mut out = String::new();
+        draw(self, "", &mut out);
+        out
+    }
+}
+
+/// Evaluate a single line, which is numbered `line` in any error
+pub fn evaluate_line<'a>(
+    line: usize,
+    source: &'a str,
+    table: &PrecedenceTable,
+) -> Result<LineResult<'a>, Error> {
+    let error = |span, kind| Error {
+        line,
+        span,
+        source: source.to_string(),
+        kind,
+    };
+
+    let expr = parse_expr(source, table).map_err(|(span, kind)| error(Some(span), kind))?;
+    let value = expr.eval().map_err(|kind| error(None, kind))?;
+    Ok(LineResult {
+        line,
+        source,
+        expr,
+        value,
+    })
+}
+
+/// Evaluate every line of the input on its own
+pub fn evaluate_lines<'a>(
+    input: &'a str,
+    table: &PrecedenceTable,
+) -> Result<Vec<LineResult<'a>>, Error> {
+    lines(input)
+        .map(|(line, source)| evaluate_line(line, source, table))
+        .collect()
+}
+
+/// Evaluate every line of the input, and sum the results
+fn evaluate(input: &str, table: &PrecedenceTable) -> Result<i64, Error> {
+    evaluate_lines(input, table)?
+        .into_iter()
+        .try_fold(0i64, |sum, result| {
+            Op::Add.apply(sum, result.value).map_err(|kind| Error {
+                line: result.line,
+                span: None,
+                source: result.source.to_string(),
+                kind,
+            })
+        })
+}
+
+/// Evaluate the input, and panic with the offending line if anything goes wrong
+fn solve(input: &str, table: &PrecedenceTable) -> u64 {
+    let sum = evaluate(input, table).unwrap_or_else(|err| panic!("{}", err));
+    u64::try_from(sum).expect("the sum is negative")
+}
+
+/// Prints the expression with every operation in parentheses, to show how it's grouped
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Num(num) => write!(f, "{}", num),
+            Expr::Op(op, lhs, rhs) => write!(f, "({} {} {})", lhs, op, rhs),
+            Expr::Neg(expr) => write!(f, "(-{})", expr),
+        }
+    }
+}
+
+impl fmt::Display for LineResult<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}", self.expr, self.value)
+    }
+}
+
+impl fmt::Display for RPN {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RPN::Op(op) => write!(f, "{}", op),
+            RPN::Num(num) => write!(f, "{}", num),
+            RPN::Neg => write!(f, "neg"),
+        }
     }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Add => write!(f, "+"),
+            Op::Sub => write!(f, "-"),
+            Op::Mul => write!(f, "*"),
+            Op::Div => write!(f, "/"),
+            Op::Rem => write!(f, "%"),
+            Op::Pow => write!(f, "^"),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "line {}, column {}: ", self.line, span.start + 1)?,
+            None => write!(f, "line {}: ", self.line)?,
+        }
+        write!(f, "{}", self.kind)?;
 
-    debug_assert_eq!(
-        stack.len(),
-        1,
-        "invalid input, stack len was {} instead of 1",
-        stack.len()
-    );
+        // point out the error on the line
+        writeln!(f)?;
+        write!(f, "    {}", self.source)?;
+        if let Some(span) = self.span {
+            let width = (span.end - span.start).max(1);
+            write!(f, "\n    {}{}", " ".repeat(span.start), "^".repeat(width))?;
+        }
 
-    stack.pop().unwrap()
+        Ok(())
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::UnexpectedChar(c) => write!(f, "unexpected char: '{}'", c),
+            ErrorKind::NumberTooLarge(num) => write!(f, "number is too large: {}", num),
+            ErrorKind::UnclosedParen => write!(f, "unclosed parenthesis"),
+            ErrorKind::UnmatchedParen => write!(f, "unmatched closing parenthesis"),
+            ErrorKind::MissingOperand => write!(f, "missing operand"),
+            ErrorKind::MissingOperator => write!(f, "missing operator"),
+            ErrorKind::TrailingOperator(op) => write!(f, "trailing operator '{}'", op),
+            ErrorKind::Overflow(op, lhs, rhs) => {
+                write!(f, "overflow when evaluating {} {} {}", lhs, op, rhs)
+            }
+            ErrorKind::DivisionByZero => write!(f, "division by zero"),
+            ErrorKind::NegativeExponent(exp) => write!(f, "negative exponent: {}", exp),
+        }
+    }
 }
 
 pub fn part1(input: &str) -> u64 {
-    let rpn = to_rpn(
-        input,
-        |_, _| false, /* no operator has a higher prescedence */
-    );
-    evalutate_rpn(&rpn)
+    solve(input, &PrecedenceTable::part1())
 }
 
 pub fn part2(input: &str) -> u64 {
-    let rpn = to_rpn(
-        input,
-        |op1, op2| (op1, op2) == (Op::Add, Op::Mul), // + binds more tightly than *
-    );
-    evalutate_rpn(&rpn)
+    solve(input, &PrecedenceTable::part2())
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{
+        evaluate, evaluate_line, evaluate_lines, parse_expr, parse_rpn, part1, part2, tokenize,
+        Assoc, Error, ErrorKind, Op, PrecedenceTable, Span, Token,
+    };
+
+    /// Tokenize a line, without the spans
+    fn tokens(line: &str) -> Result<Vec<Token>, ErrorKind> {
+        match tokenize(line) {
+            Ok(tokens) => Ok(tokens.into_iter().map(|(_, token)| token).collect()),
+            Err((_, kind)) => Err(kind),
+        }
+    }
 
     #[test]
     pub fn test_part1() {
@@ -180,4 +664,337 @@ mod tests {
         let input = include_str!("test-input");
         assert_eq!(part2(input), 693891);
     }
+
+    #[test]
+    pub fn test_tokenize() {
+        assert_eq!(
+            tokens("12 * (-3 + 405)"),
+            Ok(vec![
+                Token::Num(12),
+                Token::Op(Op::Mul),
+                Token::LParen,
+                Token::Num(-3),
+                Token::Op(Op::Add),
+                Token::Num(405),
+                Token::RParen,
+            ])
+        );
+        assert_eq!(
+            tokens("12 - -3"),
+            Ok(vec![Token::Num(12), Token::Op(Op::Sub), Token::Num(-3)])
+        );
+        assert_eq!(
+            tokens("12-3"),
+            Ok(vec![Token::Num(12), Token::Op(Op::Sub), Token::Num(3)])
+        );
+        assert_eq!(tokens("- 3"), Ok(vec![Token::Op(Op::Sub), Token::Num(3)]));
+        assert_eq!(tokens("2 & 3"), Err(ErrorKind::UnexpectedChar('&')));
+        assert_eq!(
+            tokens("99999999999999999999"),
+            Err(ErrorKind::NumberTooLarge(
+                "99999999999999999999".to_string()
+            ))
+        );
+
+        let span = |start, end| Span { start, end };
+        assert_eq!(
+            tokenize("(12 + -3)"),
+            Ok(vec![
+                (span(0, 1), Token::LParen),
+                (span(1, 3), Token::Num(12)),
+                (span(4, 5), Token::Op(Op::Add)),
+                (span(6, 8), Token::Num(-3)),
+                (span(8, 9), Token::RParen),
+            ])
+        );
+    }
+
+    #[test]
+    pub fn test_multi_digit() {
+        assert_eq!(part1("12 + 3 * 10"), 150);
+        assert_eq!(part2("12 + 3 * 10"), 150);
+        assert_eq!(part2("10 * 3 + 12"), 150);
+        assert_eq!(evaluate("-4 * 5 + -6", &PrecedenceTable::part1()), Ok(-26));
+    }
+
+    #[test]
+    pub fn test_overflow() {
+        let input = "1 + 2\n4294967296 * 4294967296\n";
+        assert_eq!(
+            evaluate(input, &PrecedenceTable::part1()),
+            Err(Error {
+                line: 2,
+                span: None,
+                source: "4294967296 * 4294967296".to_string(),
+                kind: ErrorKind::Overflow(Op::Mul, 4294967296, 4294967296),
+            })
+        );
+
+        // the sum of the lines may also overflow
+        let input = "9223372036854775807\n1";
+        assert_eq!(
+            evaluate(input, &PrecedenceTable::part1())
+                .unwrap_err()
+                .to_string(),
+            "line 2: overflow when evaluating 9223372036854775807 + 1\n    1"
+        );
+    }
+
+    #[test]
+    pub fn test_standard_precedence() {
+        // expected values follow the usual rules of arithmetic
+        let examples = [
+            ("1 + 2 * 3", 7),
+            ("2 * 3 + 4 * 5", 26),
+            ("(1 + 2) * 3", 9),
+            ("10 - 4 - 3", 3),
+            ("1 - 2 * 3 + 4", -1),
+            ("100 / 10 / 5", 2),
+            ("7 % 4 * 3", 9),
+            ("17 - 10 / 3 % 2", 16),
+            ("2 ^ 3 ^ 2", 512),
+            ("2 * 3 ^ 2", 18),
+            ("1 + 2 * 3 ^ 2 - 4", 15),
+            ("2 ^ 10 - 1000", 24),
+            ("(2 ^ 3) ^ 2", 64),
+            ("-7 / 2", -3),
+            ("-2 ^ 2", -4),
+            ("0 - 2 ^ 2", -4),
+            ("2 * -3 ^ 2", -18),
+            ("-2 ^ 2 * 3", -12),
+            ("-2 ^ 3 ^ 2", -512),
+            ("(-2) ^ 2", 4),
+        ];
+
+        let table = PrecedenceTable::standard();
+        for &(input, output) in examples.iter() {
+            assert_eq!(evaluate(input, &table), Ok(output), "input was: {}", input);
+        }
+    }
+
+    #[test]
+    pub fn test_custom_precedence() {
+        let table = PrecedenceTable::uniform();
+        assert_eq!(evaluate("2 ^ 3 * 2 - 1", &table), Ok(15));
+        assert_eq!(evaluate("10 - 4 - 3", &table), Ok(3));
+
+        // subtraction grouping right to left
+        let table = table
+            .with(Op::Sub, 0, Assoc::Right)
+            .with(Op::Add, 0, Assoc::Right);
+        assert_eq!(evaluate("10 - 4 - 3", &table), Ok(9));
+        assert_eq!(evaluate("10 - 4 + 3", &table), Ok(3));
+
+        // multiplication binding more tightly than exponentiation
+        let table = PrecedenceTable::standard().with(Op::Mul, 4, Assoc::Left);
+        assert_eq!(evaluate("2 ^ 3 * 2", &table), Ok(64));
+
+        let table = PrecedenceTable::part2();
+        assert_eq!(evaluate("2 * 3 - 1", &table), Ok(4));
+
+        // the sign of a number binds most tightly, unless the table puts it lower
+        assert_eq!(evaluate("-2 ^ 2", &PrecedenceTable::part1()), Ok(4));
+        assert_eq!(evaluate("-2 + 3 * 4", &PrecedenceTable::part2()), Ok(4));
+        let table = PrecedenceTable::part2().with_negation(0);
+        assert_eq!(evaluate("-2 + 3 * 4", &table), Ok(-20));
+    }
+
+    #[test]
+    pub fn test_arithmetic_errors() {
+        let table = PrecedenceTable::standard();
+        let error = |input| evaluate(input, &table).unwrap_err().kind;
+        assert_eq!(error("1 / (2 - 2)"), ErrorKind::DivisionByZero);
+        assert_eq!(error("1 % 0"), ErrorKind::DivisionByZero);
+        assert_eq!(error("2 ^ -1"), ErrorKind::NegativeExponent(-1));
+        assert_eq!(error("2 ^ 63"), ErrorKind::Overflow(Op::Pow, 2, 63));
+        assert_eq!(
+            error("-9223372036854775808 / -1"),
+            ErrorKind::Overflow(Op::Div, i64::MIN, -1)
+        );
+
+        // the sign has to wait for the `^`, so the number on its own is too large
+        assert_eq!(
+            error("-9223372036854775808 ^ 1"),
+            ErrorKind::NumberTooLarge("9223372036854775808".to_string())
+        );
+    }
+
+    #[test]
+    pub fn test_syntax_errors() {
+        let table = PrecedenceTable::standard();
+        let error = |input| evaluate(input, &table).unwrap_err();
+        let span = |start, end| Some(Span { start, end });
+
+        let examples = [
+            ("(1 + 2", span(0, 1), ErrorKind::UnclosedParen),
+            ("1 + (2 * (3 + 4)", span(4, 5), ErrorKind::UnclosedParen),
+            ("1 + 2)", span(5, 6), ErrorKind::UnmatchedParen),
+            ("(1 + 2)) * (3", span(7, 8), ErrorKind::UnmatchedParen),
+            ("1 + * 2", span(4, 5), ErrorKind::MissingOperand),
+            ("* 2", span(0, 1), ErrorKind::MissingOperand),
+            ("- 2", span(0, 1), ErrorKind::MissingOperand),
+            ("() + 1", span(1, 2), ErrorKind::MissingOperand),
+            ("1 2", span(2, 3), ErrorKind::MissingOperator),
+            ("2 (3 + 4)", span(2, 3), ErrorKind::MissingOperator),
+            ("1 +", span(2, 3), ErrorKind::TrailingOperator(Op::Add)),
+            (
+                "(1 *) + 2",
+                span(3, 4),
+                ErrorKind::TrailingOperator(Op::Mul),
+            ),
+            ("1 + 2x", span(5, 6), ErrorKind::UnexpectedChar('x')),
+        ];
+
+        for (input, span, kind) in examples.iter().cloned() {
+            let error = error(input);
+            assert_eq!(
+                (error.span, error.kind),
+                (span, kind),
+                "input was: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_diagnostic() {
+        let input = "1 + 2\n3 * (4 + 5 +) * 6\n";
+        let error = evaluate(input, &PrecedenceTable::part1()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 12: trailing operator '+'\n\
+             \x20   3 * (4 + 5 +) * 6\n\
+             \x20              ^"
+        );
+
+        let input = "12345678901234567890123 + 1";
+        let error = evaluate(input, &PrecedenceTable::part1()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1, column 1: number is too large: 12345678901234567890123\n\
+             \x20   12345678901234567890123 + 1\n\
+             \x20   ^^^^^^^^^^^^^^^^^^^^^^^"
+        );
+    }
+
+    #[test]
+    pub fn test_evaluate_lines() {
+        let input = include_str!("test-input");
+        let values = |table| -> Vec<(usize, i64)> {
+            evaluate_lines(input, &table)
+                .unwrap()
+                .iter()
+                .map(|result| (result.line, result.value))
+                .collect()
+        };
+
+        assert_eq!(
+            values(PrecedenceTable::part1()),
+            vec![(1, 26), (2, 437), (3, 12240), (4, 13632)]
+        );
+        assert_eq!(
+            values(PrecedenceTable::part2()),
+            vec![(1, 46), (2, 1445), (3, 669060), (4, 23340)]
+        );
+
+        // empty lines are skipped, but still counted
+        let results = evaluate_lines("1 + 2\n\n3 * 4\n", &PrecedenceTable::part1()).unwrap();
+        assert_eq!(results[1].line, 3);
+        assert_eq!(results[1].source, "3 * 4");
+    }
+
+    #[test]
+    pub fn test_grouping() {
+        let input = "1 + 2 * 3 + 4 * 5 + 6";
+        let grouped = |table| parse_expr(input, &table).unwrap().to_string();
+        assert_eq!(
+            grouped(PrecedenceTable::part1()),
+            "(((((1 + 2) * 3) + 4) * 5) + 6)"
+        );
+        assert_eq!(
+            grouped(PrecedenceTable::part2()),
+            "(((1 + 2) * (3 + 4)) * (5 + 6))"
+        );
+        assert_eq!(
+            grouped(PrecedenceTable::standard()),
+            "(((1 + (2 * 3)) + (4 * 5)) + 6)"
+        );
+
+        let results = evaluate_lines("2 ^ 3 ^ 2 - -4", &PrecedenceTable::part1()).unwrap();
+        assert_eq!(results[0].to_string(), "(((2 ^ 3) ^ 2) - -4) = 68");
+    }
+
+    #[test]
+    pub fn test_rpn() {
+        let rpn = |line, table| -> Vec<String> {
+            parse_rpn(line, &table)
+                .unwrap()
+                .iter()
+                .map(|op| op.to_string())
+                .collect()
+        };
+        assert_eq!(
+            rpn("1 + 2 * 3", PrecedenceTable::part1()).join(" "),
+            "1 2 + 3 *"
+        );
+        assert_eq!(
+            rpn("1 + 2 * 3", PrecedenceTable::standard()).join(" "),
+            "1 2 3 * +"
+        );
+        assert_eq!(
+            rpn("(2 ^ 3) ^ -2", PrecedenceTable::standard()).join(" "),
+            "2 3 ^ -2 ^"
+        );
+        assert_eq!(
+            rpn("2 * -3 ^ 2", PrecedenceTable::standard()).join(" "),
+            "2 3 2 ^ neg *"
+        );
+        assert_eq!(
+            rpn("2 * -3 ^ 2", PrecedenceTable::part1()).join(" "),
+            "2 -3 * 2 ^"
+        );
+        assert_eq!(
+            parse_rpn("1 + * 2", &PrecedenceTable::part1()),
+            Err((Span { start: 4, end: 5 }, ErrorKind::MissingOperand))
+        );
+    }
+
+    #[test]
+    pub fn test_tree() {
+        let expr = parse_expr("1 + 2 * (3 - 4)", &PrecedenceTable::standard()).unwrap();
+        assert_eq!(
+            expr.tree(),
+            "+\n\
+             ├── 1\n\
+             └── *\n\
+             \x20   ├── 2\n\
+             \x20   └── -\n\
+             \x20       ├── 3\n\
+             \x20       └── 4\n"
+        );
+        assert_eq!(
+            parse_expr("7", &PrecedenceTable::part1()).unwrap().tree(),
+            "7\n"
+        );
+
+        let expr = parse_expr("-2 ^ 2", &PrecedenceTable::standard()).unwrap();
+        assert_eq!(expr.to_string(), "(-(2 ^ 2))");
+        assert_eq!(
+            expr.tree(),
+            "neg\n\
+             └── ^\n\
+             \x20   ├── 2\n\
+             \x20   └── 2\n"
+        );
+    }
+
+    #[test]
+    pub fn test_evaluate_line() {
+        let table = PrecedenceTable::part2();
+        assert_eq!(evaluate_line(5, "2 * 3 + 4", &table).unwrap().value, 14);
+
+        let error = evaluate_line(5, "2 * (3 + 4", &table).unwrap_err();
+        assert_eq!(error.line, 5);
+        assert_eq!(error.kind, ErrorKind::UnclosedParen);
+    }
 }
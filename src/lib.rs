@@ -0,0 +1,22 @@
+//! The solutions of every day, shared by the runner and the companion binaries in `src/bin`
+
+#![feature(test)]
+extern crate test;
+
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day07;
+pub mod day08;
+pub mod day09;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;